@@ -1,3 +1,4 @@
+use colored::Colorize;
 use inquire::InquireError;
 
 use crate::{files::note, options::opts};
@@ -6,25 +7,43 @@ use crate::{files::note, options::opts};
 pub(crate) struct Ctx {
     pub opts: opts::Opts,
     pub notes: Vec<note::Note>,
+    // Files in the silos without a denote name, only offered for renaming
+    pub other_files: Vec<note::Note>,
     pub keywords: Vec<String>,
 }
 
 impl Ctx {
     pub fn new() -> Result<Self, InquireError> {
         let opts = opts::load()?;
-        let notes = note::load(&opts.note_dir)?;
+        let silos = opts.all_silos();
+
+        // Only label notes with their silo when there is more than one
+        let mut notes = Vec::new();
+        let mut other_files = Vec::new();
+        for silo in &silos {
+            // An unmounted silo shouldn't lock the user out, not even of --config
+            if !silo.path.is_dir() {
+                eprintln!(
+                    "{} Skipping silo {}, {:?} is not a folder",
+                    "#".red(),
+                    silo.name,
+                    silo.path
+                );
+                continue;
+            }
+
+            let label = (silos.len() > 1).then(|| silo.name.clone());
+            let (silo_notes, silo_others) = note::load(&silo.path, &opts.exclude)?;
+
+            notes.extend(silo_notes.into_iter().map(|note| note.in_silo(label.clone())));
+            other_files.extend(silo_others.into_iter().map(|note| note.in_silo(label.clone())));
+        }
 
         Ok(Self {
             opts,
             keywords: note::parse_all_keywords(&notes),
             notes,
+            other_files,
         })
     }
 }
-
-pub(crate) fn with_ctx<F, R>(ctx: Ctx, f: F) -> R
-where
-    F: FnOnce(&Ctx) -> R,
-{
-    f(&ctx)
-}
@@ -0,0 +1,238 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
+
+use crate::{
+    ctx,
+    files::{frontmatter, name, note, types},
+    options::template,
+};
+
+// --- New notes ---
+// Everything decided about a note before it is written, however it was asked for
+#[derive(Debug, Clone)]
+pub(crate) struct Draft {
+    pub title: String,
+    pub keywords: Vec<String>,
+    pub signature: Option<String>,
+    pub time: DateTime<Local>,
+    pub dir: PathBuf,
+    pub file_type: types::FileType,
+    pub template: Option<template::Template>,
+    // Answers to the template's variables
+    pub answers: Vec<(String, String)>,
+}
+
+impl Draft {
+    // A draft for right now in the default silo, with nothing filled in yet
+    pub fn new(ctx: &ctx::Ctx) -> Self {
+        Self {
+            title: String::new(),
+            keywords: Vec::new(),
+            signature: None,
+            time: Local::now(),
+            dir: ctx.opts.note_dir.clone(),
+            file_type: ctx.opts.notes_filetype,
+            template: None,
+            answers: Vec::new(),
+        }
+    }
+
+    // Returns the path, front matter and body of the note
+    pub fn prepare(self, ctx: &ctx::Ctx) -> (PathBuf, frontmatter::FrontMatter, String) {
+        // The front matter keeps the title as typed, only the file name is slugified
+        let mut name = name::DenoteName {
+            identifier: Some(self.time.format(name::ID_FORMAT).to_string()),
+            signature: self.signature,
+            title: Some(name::slugify_title(&self.title)).filter(|title| !title.is_empty()),
+            keywords: self.keywords.clone(),
+            extension: self.file_type.as_ref().to_string(),
+            order: name::normalise_order(&ctx.opts.name_order),
+        };
+
+        name::warn_if_shortened(&mut name);
+
+        // Notes made within the same second would otherwise share an identifier
+        note::ensure_unique(&ctx.notes, &self.dir, &mut name);
+
+        let fmt = frontmatter::FrontMatter {
+            title: self.title,
+            date: frontmatter::format_date(self.file_type, ctx.opts.denote_compatible, &self.time),
+            file_tags: self.keywords,
+            indentifier: name.identifier.clone().unwrap_or_default(),
+            extra: self.answers,
+        };
+
+        let body = self
+            .template
+            .map(|template| {
+                let mut vars = vec![
+                    ("title", fmt.title.clone()),
+                    ("date", fmt.date.clone()),
+                    ("identifier", fmt.indentifier.clone()),
+                    ("keywords", fmt.file_tags.join(" ")),
+                    ("signature", name.signature.clone().unwrap_or_default()),
+                ];
+                vars.extend(fmt.extra.iter().map(|(key, value)| (key.as_str(), value.clone())));
+
+                template.render(&vars)
+            })
+            .unwrap_or_default();
+
+        (self.dir.join(name.to_string()), fmt, body)
+    }
+}
+
+// --- Dates ---
+// A day and time of day as a local timestamp
+pub(crate) fn local_time(date: NaiveDate, time: NaiveTime) -> DateTime<Local> {
+    let naive = date.and_time(time);
+
+    naive.and_local_timezone(Local).earliest().unwrap_or_else(|| {
+        // Times in a daylight saving gap don't exist, so they are read with the offset from
+        // before it and move forward like a clock would: 01:30 becomes 02:30
+        let offset = (naive - TimeDelta::days(1))
+            .and_local_timezone(Local)
+            .earliest()
+            .map_or(0, |before| before.offset().local_minus_utc());
+
+        (naive - TimeDelta::seconds(offset.into()))
+            .and_utc()
+            .with_timezone(&Local)
+    })
+}
+
+// Accepts 2024-03-22, 2024-03-22 14:30, 2024-03-22T14:30:00 or an identifier
+// A bare day keeps the current time of day, like journal entries
+pub(crate) fn parse_time(input: &str) -> Option<DateTime<Local>> {
+    let input = input.trim();
+
+    if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        return Some(local_time(date, Local::now().time()));
+    }
+
+    ["%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", name::ID_FORMAT]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(input, fmt).ok())
+        .map(|time| local_time(time.date(), time.time()))
+}
+
+// The first date found in a file name, e.g. IMG_20240322_141500.jpg or scan 2024-03-22.pdf
+// A bare day is taken as midnight
+pub(crate) fn time_from_file_name(file_name: &str) -> Option<DateTime<Local>> {
+    const DATE_TIMES: [(&str, usize); 4] = [
+        (name::ID_FORMAT, 15),
+        ("%Y%m%d_%H%M%S", 15),
+        ("%Y%m%d-%H%M%S", 15),
+        ("%Y-%m-%d_%H-%M-%S", 19),
+    ];
+    const DATES: [(&str, usize); 3] = [("%Y-%m-%d", 10), ("%Y_%m_%d", 10), ("%Y%m%d", 8)];
+
+    // chrono skips whitespace before numbers, which would read " 2024-03-2" as a date
+    let candidate = |start: usize, len: usize| {
+        file_name
+            .get(start..start + len)
+            .filter(|part| !part.contains(char::is_whitespace))
+    };
+
+    (0..file_name.len())
+        .filter(|start| file_name.as_bytes()[*start].is_ascii_digit())
+        .find_map(|start| {
+            let date_time = DATE_TIMES.iter().find_map(|(fmt, len)| {
+                NaiveDateTime::parse_from_str(candidate(start, *len)?, fmt).ok()
+            });
+            let date = || {
+                DATES.iter().find_map(|(fmt, len)| {
+                    NaiveDate::parse_from_str(candidate(start, *len)?, fmt)
+                        .ok()
+                        .map(|date| date.and_time(NaiveTime::MIN))
+                })
+            };
+
+            date_time.or_else(date)
+        })
+        .map(|time| local_time(time.date(), time.time()))
+}
+
+// Where an identifier for a file without one can come from, best guess first
+pub(crate) fn identifier_sources(path: &Path) -> std::io::Result<Vec<(&'static str, DateTime<Local>)>> {
+    let file_name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    let modified = DateTime::<Local>::from(fs::metadata(path)?.modified()?);
+
+    let mut sources = Vec::with_capacity(3);
+    if let Some(time) = time_from_file_name(file_name) {
+        sources.push(("Date in the file name", time));
+    }
+    sources.push(("File modification time", modified));
+    sources.push(("Now", Local::now()));
+
+    Ok(sources)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A directory of its own for each test, removed when the test ends
+    struct Scratch(PathBuf);
+
+    impl Scratch {
+        fn new(test: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("decoy-{}-{}", test, std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            Scratch(dir)
+        }
+
+        fn file(&self, file_name: &str) -> PathBuf {
+            let path = self.0.join(file_name);
+            fs::write(&path, "").unwrap();
+            path
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn dates_in_file_names() {
+        let at = |y, m, d, h, min, s| {
+            local_time(
+                NaiveDate::from_ymd_opt(y, m, d).unwrap(),
+                NaiveTime::from_hms_opt(h, min, s).unwrap(),
+            )
+        };
+
+        assert_eq!(time_from_file_name("IMG_20240322_141500.jpg"), Some(at(2024, 3, 22, 14, 15, 0)));
+        assert_eq!(time_from_file_name("scan 2024-03-22.pdf"), Some(at(2024, 3, 22, 0, 0, 0)));
+        assert_eq!(time_from_file_name("café_2023_01_05.png"), Some(at(2023, 1, 5, 0, 0, 0)));
+        assert_eq!(time_from_file_name("meeting-notes.md"), None);
+        assert_eq!(time_from_file_name("v12345678.txt"), None);
+    }
+
+    #[test]
+    fn files_without_an_identifier_offer_their_modification_time() {
+        let scratch = Scratch::new("modification-time");
+        let path = scratch.file("meeting-notes.md");
+        let sources = identifier_sources(&path).unwrap();
+        let labels: Vec<&str> = sources.iter().map(|(label, _)| *label).collect();
+
+        assert_eq!(labels, ["File modification time", "Now"]);
+    }
+
+    #[test]
+    fn dates_in_the_name_come_first() {
+        let scratch = Scratch::new("dates-first");
+        let path = scratch.file("scan 2024-03-22.pdf");
+        let sources = identifier_sources(&path).unwrap();
+
+        assert_eq!(sources[0].0, "Date in the file name");
+        assert_eq!(sources[0].1.format(name::ID_FORMAT).to_string(), "20240322T000000");
+        assert_eq!(sources.len(), 3);
+    }
+}
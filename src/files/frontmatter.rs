@@ -1,11 +1,43 @@
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Deserializer, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::files::types;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct FrontMatter {
     pub title: String,
     pub date: String,
+    // Denote itself writes `tags` and `identifier`
+    #[serde(alias = "tags", deserialize_with = "tags_from_list_or_string")]
     pub file_tags: Vec<String>,
+    #[serde(alias = "identifier")]
     pub indentifier: String,
+    // Answers to template variables, written after the fields above
+    #[serde(skip)]
+    pub extra: Vec<(String, String)>,
+}
+
+pub fn to_yaml_front_matter(fmt: FrontMatter) -> Result<String, serde_yaml::Error> {
+    let mut lines = vec![serde_yaml::to_string(&fmt)?.trim_end().to_string()];
+    lines.extend(extra_lines(Syntax::Yaml, &fmt.extra));
+
+    Ok(format!("---\n{}\n---\n", lines.join("\n")))
+}
+
+// Hugo and Zola expect `tags` and a real TOML datetime, so the date is left unquoted
+pub fn to_toml_front_matter(fmt: FrontMatter) -> String {
+    let tags: Vec<String> = fmt.file_tags.iter().map(|tag| quote(tag)).collect();
+
+    let mut lines = vec![];
+
+    lines.push(format!("title = {}", quote(&fmt.title)));
+    lines.push(format!("date = {}", fmt.date));
+    lines.push(format!("tags = [{}]", tags.join(", ")));
+    lines.push(format!("identifier = {}", quote(&fmt.indentifier)));
+    lines.extend(extra_lines(Syntax::Toml, &fmt.extra));
+
+    format!("+++\n{}\n+++\n", lines.join("\n"))
 }
 
 pub fn to_org_front_matter(fmt: FrontMatter) -> String {
@@ -15,6 +47,764 @@ pub fn to_org_front_matter(fmt: FrontMatter) -> String {
     lines.push(format!("#+DATE: {}", fmt.date));
     lines.push(format!("#+FILETAGS: {}", fmt.file_tags.join(" ")));
     lines.push(format!("#+IDENTIFIER: {}", fmt.indentifier));
+    lines.extend(extra_lines(Syntax::Org, &fmt.extra));
 
     lines.join("\n")
 }
+
+// The header Emacs Denote uses for plain text notes, closed by a line of dashes
+pub fn to_text_front_matter(fmt: FrontMatter) -> String {
+    let mut lines = vec![];
+
+    lines.push(format!("title:      {}", fmt.title));
+    lines.push(format!("date:       {}", fmt.date));
+    lines.push(format!("tags:       {}", fmt.file_tags.join("  ")));
+    lines.push(format!("identifier: {}", fmt.indentifier));
+    lines.extend(extra_lines(Syntax::Text, &fmt.extra));
+    lines.push("-".repeat(27));
+
+    lines.join("\n") + "\n"
+}
+
+// Typst has no front matter, so the document metadata is set and the rest kept in comments
+pub fn to_typst_front_matter(fmt: FrontMatter) -> String {
+    let mut lines = vec![];
+
+    lines.push("#set document(".to_string());
+    lines.push(format!("  title: {},", quote(&fmt.title)));
+    lines.push(format!("  keywords: {},", typst_array(&fmt.file_tags)));
+    lines.push(")".to_string());
+    lines.push(format!("// date: {}", fmt.date));
+    lines.push(format!("// identifier: {}", fmt.indentifier));
+    lines.extend(extra_lines(Syntax::Typst, &fmt.extra));
+
+    lines.join("\n") + "\n"
+}
+
+// Keys every header already writes, a template variable can not replace them
+const RESERVED_KEYS: [&str; 7] = [
+    "title",
+    "date",
+    "tags",
+    "filetags",
+    "file_tags",
+    "identifier",
+    "indentifier",
+];
+
+fn is_reserved(key: &str) -> bool {
+    RESERVED_KEYS.iter().any(|reserved| reserved.eq_ignore_ascii_case(key))
+}
+
+// One `key: value` line per extra field, in the syntax of the header
+fn extra_lines(syntax: Syntax, extra: &[(String, String)]) -> Vec<String> {
+    let bare = |key: &str| key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+
+    extra
+        .iter()
+        .filter(|(key, _)| !is_reserved(key))
+        .map(|(key, value)| match syntax {
+            Syntax::Yaml if bare(key) => format!("{}: {}", key, quote(value)),
+            Syntax::Yaml => format!("{}: {}", quote(key), quote(value)),
+            Syntax::Toml if bare(key) => format!("{} = {}", key, quote(value)),
+            Syntax::Toml => format!("{} = {}", quote(key), quote(value)),
+            Syntax::Org => format!("#+{}: {}", key, value),
+            Syntax::Text => format!("{}: {}", key, value),
+            Syntax::Typst => format!("// {}: {}", key, value),
+        })
+        .collect()
+}
+
+// --- Emacs Denote compatibility ---
+// The date each Denote front matter flavour expects
+pub fn format_date(file_type: types::FileType, denote_compatible: bool, time: &DateTime<Local>) -> String {
+    match (denote_compatible, file_type) {
+        // TOML front matter always holds an RFC 3339 datetime
+        (_, types::FileType::MarkdownToml) | (true, types::FileType::Markdown) => {
+            time.format("%FT%T%:z").to_string()
+        }
+        (false, _) | (true, types::FileType::Typst) => time.format("%F %a %R").to_string(),
+        (true, types::FileType::Org) => time.format("[%F %a %R]").to_string(),
+        (true, types::FileType::Text) => time.format("%F").to_string(),
+    }
+}
+
+// Byte for byte what `denote-*-front-matter` writes, Typst has no Denote equivalent
+pub fn to_denote_front_matter(file_type: types::FileType, fmt: FrontMatter) -> String {
+    let quoted_tags = || {
+        let tags: Vec<String> = fmt.file_tags.iter().map(|tag| quote(tag)).collect();
+        format!("[{}]", tags.join(", "))
+    };
+
+    let mut lines = vec![];
+
+    match file_type {
+        types::FileType::Org => {
+            let file_tags = match fmt.file_tags.is_empty() {
+                true => String::new(),
+                false => format!(":{}:", fmt.file_tags.join(":")),
+            };
+
+            lines.push(format!("#+title:      {}", fmt.title));
+            lines.push(format!("#+date:       {}", fmt.date));
+            lines.push(format!("#+filetags:   {}", file_tags));
+            lines.push(format!("#+identifier: {}", fmt.indentifier));
+            lines.extend(extra_lines(Syntax::Org, &fmt.extra));
+        }
+        types::FileType::Markdown => {
+            lines.push("---".to_string());
+            lines.push(format!("title:      {}", quote(&fmt.title)));
+            lines.push(format!("date:       {}", fmt.date));
+            lines.push(format!("tags:       {}", quoted_tags()));
+            lines.push(format!("identifier: {}", quote(&fmt.indentifier)));
+            lines.extend(extra_lines(Syntax::Yaml, &fmt.extra));
+            lines.push("---".to_string());
+        }
+        types::FileType::MarkdownToml => {
+            lines.push("+++".to_string());
+            lines.push(format!("title      = {}", quote(&fmt.title)));
+            lines.push(format!("date       = {}", fmt.date));
+            lines.push(format!("tags       = {}", quoted_tags()));
+            lines.push(format!("identifier = {}", quote(&fmt.indentifier)));
+            lines.extend(extra_lines(Syntax::Toml, &fmt.extra));
+            lines.push("+++".to_string());
+        }
+        types::FileType::Text => {
+            lines.push(format!("title:      {}", fmt.title));
+            lines.push(format!("date:       {}", fmt.date));
+            lines.push(format!("tags:       {}", fmt.file_tags.join("  ")));
+            lines.push(format!("identifier: {}", fmt.indentifier));
+            lines.extend(extra_lines(Syntax::Text, &fmt.extra));
+            lines.push("-".repeat(27));
+        }
+        types::FileType::Typst => return to_typst_front_matter(fmt),
+    }
+
+    // Denote always leaves a blank line before the body
+    lines.join("\n") + "\n\n"
+}
+
+// --- Parsing ---
+// The header syntaxes decoy can read and rewrite
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    Yaml,
+    Toml,
+    Org,
+    Text,
+    Typst,
+}
+
+// Each parser returns the front matter and the byte offset the body starts at
+pub fn parse(file_type: types::FileType, content: &str) -> Option<(FrontMatter, usize)> {
+    detect(file_type, content).map(|(_, fm, offset)| (fm, offset))
+}
+
+pub fn detect(file_type: types::FileType, content: &str) -> Option<(Syntax, FrontMatter, usize)> {
+    let tag = |syntax: Syntax| move |(fm, offset)| (syntax, fm, offset);
+
+    match file_type {
+        types::FileType::Markdown => parse_yaml_front_matter(content)
+            .map(tag(Syntax::Yaml))
+            .or_else(|| parse_toml_front_matter(content).map(tag(Syntax::Toml))),
+        types::FileType::MarkdownToml => parse_toml_front_matter(content)
+            .map(tag(Syntax::Toml))
+            .or_else(|| parse_yaml_front_matter(content).map(tag(Syntax::Yaml))),
+        types::FileType::Org => parse_org_front_matter(content).map(tag(Syntax::Org)),
+        types::FileType::Text => parse_text_front_matter(content)
+            .map(tag(Syntax::Text))
+            .or_else(|| parse_yaml_front_matter(content).map(tag(Syntax::Yaml))),
+        types::FileType::Typst => parse_typst_front_matter(content).map(tag(Syntax::Typst)),
+    }
+}
+
+fn parse_as(syntax: Syntax, content: &str) -> Option<(FrontMatter, usize)> {
+    match syntax {
+        Syntax::Yaml => parse_yaml_front_matter(content),
+        Syntax::Toml => parse_toml_front_matter(content),
+        Syntax::Org => parse_org_front_matter(content),
+        Syntax::Text => parse_text_front_matter(content),
+        Syntax::Typst => parse_typst_front_matter(content),
+    }
+}
+
+// ---\ntitle: ...\n---
+pub fn parse_yaml_front_matter(content: &str) -> Option<(FrontMatter, usize)> {
+    let (inner, offset) = fenced(content, "---")?;
+    let fm: FrontMatter = serde_yaml::from_str(inner).ok()?;
+
+    Some((fm, offset))
+}
+
+// +++\ntitle = ...\n+++
+pub fn parse_toml_front_matter(content: &str) -> Option<(FrontMatter, usize)> {
+    let (inner, offset) = fenced(content, "+++")?;
+    let table: toml::Table = toml::from_str(inner).ok()?;
+
+    // Dates are TOML datetimes, not strings, so fields are picked out by hand
+    let text = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| table.get(*key))
+            .map(|value| match value {
+                toml::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+            .unwrap_or_default()
+    };
+
+    let file_tags = match table.get("tags").or_else(|| table.get("file_tags")) {
+        Some(toml::Value::Array(tags)) => tags
+            .iter()
+            .filter_map(|tag| tag.as_str().map(str::to_string))
+            .collect(),
+        Some(toml::Value::String(tags)) => split_tags(tags),
+        _ => Vec::new(),
+    };
+
+    let fm = FrontMatter {
+        title: text(&["title"]),
+        date: text(&["date"]),
+        file_tags,
+        indentifier: text(&["identifier", "indentifier"]),
+        ..Default::default()
+    };
+
+    Some((fm, offset))
+}
+
+// #+title: ...\n#+filetags: :a:b:
+pub fn parse_org_front_matter(content: &str) -> Option<(FrontMatter, usize)> {
+    let mut fm = FrontMatter::default();
+    let mut offset = 0;
+    let mut found = false;
+
+    for line in content.split_inclusive('\n') {
+        let Some((key, value)) = line
+            .trim_end()
+            .strip_prefix("#+")
+            .and_then(|kv| kv.split_once(':'))
+        else {
+            break;
+        };
+
+        let value = value.trim();
+        match key.to_lowercase().as_str() {
+            "title" => fm.title = value.to_string(),
+            "date" => fm.date = value.to_string(),
+            "filetags" => fm.file_tags = split_tags(value),
+            "identifier" => fm.indentifier = value.to_string(),
+            _ => {}
+        }
+
+        offset += line.len();
+        found = true;
+    }
+
+    found.then_some((fm, offset))
+}
+
+// title: ...\ntags: a b\n-----
+pub fn parse_text_front_matter(content: &str) -> Option<(FrontMatter, usize)> {
+    let mut fm = FrontMatter::default();
+    let mut offset = 0;
+    let mut found = false;
+
+    for line in content.split_inclusive('\n') {
+        offset += line.len();
+        let line = line.trim_end();
+
+        // A line of dashes closes the header
+        if line.len() >= 3 && line.chars().all(|c| c == '-') {
+            return found.then_some((fm, offset));
+        }
+
+        let (key, value) = line.split_once(':')?;
+        let value = value.trim();
+        match key.trim() {
+            "title" => fm.title = value.to_string(),
+            "date" => fm.date = value.to_string(),
+            "tags" => fm.file_tags = split_tags(value),
+            "identifier" => fm.indentifier = value.to_string(),
+            _ => {}
+        }
+        found = true;
+    }
+
+    None
+}
+
+// #set document(\n  title: "...",\n)\n// identifier: ...
+pub fn parse_typst_front_matter(content: &str) -> Option<(FrontMatter, usize)> {
+    let mut fm = FrontMatter::default();
+    let mut offset = 0;
+    let mut found = false;
+    let mut in_document = false;
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+
+        if trimmed == "#set document(" {
+            in_document = true;
+        } else if in_document && trimmed == ")" {
+            in_document = false;
+        } else {
+            // Both the document arguments and the comments are `key: value` pairs
+            let pair = match in_document {
+                true => Some(trimmed),
+                false => trimmed.strip_prefix("//").map(str::trim_start),
+            };
+            let Some((key, value)) = pair.and_then(|pair| pair.split_once(':')) else {
+                break;
+            };
+
+            let value = value.trim().trim_end_matches(',');
+            match key.trim() {
+                "title" => fm.title = unquote(value),
+                "date" => fm.date = value.to_string(),
+                "keywords" => {
+                    fm.file_tags = value
+                        .trim_start_matches('(')
+                        .trim_end_matches(')')
+                        .split(',')
+                        .map(|tag| unquote(tag.trim()))
+                        .filter(|tag| !tag.is_empty())
+                        .collect()
+                }
+                "identifier" => fm.indentifier = value.to_string(),
+                _ => {}
+            }
+            found = true;
+        }
+
+        offset += line.len();
+    }
+
+    found.then_some((fm, offset))
+}
+
+// --- Rewriting ---
+// Fields to change in an existing header, None leaves the field as it is
+#[derive(Debug, Default)]
+pub struct Changes {
+    pub title: Option<String>,
+    pub file_tags: Option<Vec<String>>,
+    pub identifier: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Title,
+    Tags,
+    Identifier,
+}
+
+// Rewrites the matching header lines in place, other fields and the body are kept byte for byte
+pub fn rewrite(syntax: Syntax, content: &str, offset: usize, changes: &Changes) -> String {
+    // Values that are already right keep their line, quoting and all
+    let old = parse_as(syntax, content).map(|(fm, _)| fm).unwrap_or_default();
+    let title = changes.title.as_deref().filter(|title| *title != old.title);
+    let file_tags = changes.file_tags.as_deref().filter(|tags| *tags != old.file_tags);
+    let identifier = changes.identifier.as_deref().filter(|id| *id != old.indentifier);
+
+    let (header, body) = content.split_at(offset);
+    let mut lines = header.split_inclusive('\n').peekable();
+    let mut rewritten = String::with_capacity(content.len());
+
+    while let Some(line) = lines.next() {
+        let Some((field, prefix, value, suffix)) = split_field(syntax, line) else {
+            rewritten.push_str(line);
+            continue;
+        };
+
+        let new_value = match field {
+            Field::Title => title.map(|t| format_text(syntax, value, t)),
+            Field::Identifier => identifier.map(|id| format_text(syntax, value, id)),
+            Field::Tags => file_tags.map(|tags| {
+                // Block lists and multi-line arrays are replaced as a whole
+                let mut item_prefix = None;
+                if syntax == Syntax::Yaml && value.is_empty() {
+                    while let Some(item) = lines.next_if(|l| l.trim_start().starts_with("- ")) {
+                        item_prefix.get_or_insert_with(|| {
+                            item[..item.len() - item.trim_start().len() + 2].to_string()
+                        });
+                    }
+                } else if value.starts_with('[') && !value.contains(']') {
+                    for item in lines.by_ref() {
+                        if item.contains(']') {
+                            break;
+                        }
+                    }
+                }
+
+                match item_prefix {
+                    Some(item_prefix) => tags
+                        .iter()
+                        .map(|tag| format!("\n{}{}", item_prefix, tag))
+                        .collect(),
+                    None => format_tags(syntax, value, tags),
+                }
+            }),
+        };
+
+        match new_value {
+            Some(new_value) => {
+                let newline = if line.ends_with('\n') { "\n" } else { "" };
+                let prefix = match new_value.starts_with('\n') {
+                    true => prefix.trim_end(),
+                    false => prefix,
+                };
+                rewritten.push_str(prefix);
+                rewritten.push_str(&new_value);
+                rewritten.push_str(suffix);
+                rewritten.push_str(newline);
+            }
+            None => rewritten.push_str(line),
+        }
+    }
+
+    rewritten.push_str(body);
+    rewritten
+}
+
+// Splits a header line into its field, the key with its spacing, the value and any trailing ','
+fn split_field(syntax: Syntax, line: &str) -> Option<(Field, &str, &str, &str)> {
+    let line = line.trim_end_matches(['\n', '\r']);
+
+    let (key, separator) = match syntax {
+        Syntax::Org => (line.strip_prefix("#+")?.split_once(':')?.0, ':'),
+        Syntax::Toml => (line.split_once('=')?.0, '='),
+        Syntax::Yaml | Syntax::Text => (line.split_once(':')?.0, ':'),
+        // Document arguments are indented and the rest sits in comments
+        Syntax::Typst => {
+            let trimmed = line.trim_start();
+            let trimmed = trimmed.strip_prefix("//").map_or(trimmed, str::trim_start);
+            (trimmed.split_once(':')?.0, ':')
+        }
+    };
+
+    // Nested YAML keys are indented, only top level fields count
+    if key.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let field = match (syntax, key.trim().to_lowercase().as_str()) {
+        (_, "title") => Field::Title,
+        (_, "tags" | "file_tags" | "filetags") | (Syntax::Typst, "keywords") => Field::Tags,
+        (_, "identifier" | "indentifier") => Field::Identifier,
+        _ => return None,
+    };
+
+    let value_start = line.find(separator)? + 1;
+    let value = line[value_start..].trim_start();
+    let prefix = &line[..line.len() - value.len()];
+    let value = value.trim_end();
+
+    let (value, suffix) = match syntax {
+        Syntax::Typst if value.ends_with(',') => (&value[..value.len() - 1], ","),
+        _ => (value, ""),
+    };
+
+    Some((field, prefix, value, suffix))
+}
+
+// Keeps the quoting style of the old value
+fn format_text(syntax: Syntax, old: &str, new: &str) -> String {
+    match syntax {
+        Syntax::Org | Syntax::Text => new.to_string(),
+        Syntax::Typst if old.starts_with('"') => quote(new),
+        Syntax::Typst => new.to_string(),
+        Syntax::Toml => toml::Value::String(new.to_string()).to_string(),
+        Syntax::Yaml if old.starts_with('"') => quote(new),
+        Syntax::Yaml => serde_yaml::to_string(new)
+            .map(|yaml| yaml.trim_end().to_string())
+            .unwrap_or_else(|_| quote(new)),
+    }
+}
+
+fn format_tags(syntax: Syntax, old: &str, tags: &[String]) -> String {
+    match syntax {
+        // Denote writes ":a:b:", older decoy notes use "a b"
+        Syntax::Org if old.starts_with(':') || old.is_empty() => match tags.is_empty() {
+            true => String::new(),
+            false => format!(":{}:", tags.join(":")),
+        },
+        Syntax::Org => tags.join(" "),
+        Syntax::Text => tags.join("  "),
+        Syntax::Typst => typst_array(tags),
+        Syntax::Yaml | Syntax::Toml => format!(
+            "[{}]",
+            tags.iter().map(|tag| quote(tag)).collect::<Vec<_>>().join(", ")
+        ),
+    }
+}
+
+// Typst needs a trailing comma to tell a one item array from parentheses
+fn typst_array(items: &[String]) -> String {
+    match items {
+        [item] => format!("({},)", quote(item)),
+        _ => format!(
+            "({})",
+            items.iter().map(|item| quote(item)).collect::<Vec<_>>().join(", ")
+        ),
+    }
+}
+
+fn unquote(text: &str) -> String {
+    match text.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+        Some(inner) => inner.replace("\\\"", "\"").replace("\\\\", "\\"),
+        None => text.to_string(),
+    }
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// --- Parsing helpers ---
+// Returns what sits between the opening and closing fence, and the offset after the closing one
+fn fenced<'a>(content: &'a str, fence: &str) -> Option<(&'a str, usize)> {
+    let mut lines = content.split_inclusive('\n');
+
+    let opening = lines.next()?;
+    if opening.trim_end() != fence {
+        return None;
+    }
+
+    let start = opening.len();
+    let mut end = start;
+
+    for line in lines {
+        if line.trim_end() == fence {
+            return Some((&content[start..end], end + line.len()));
+        }
+        end += line.len();
+    }
+
+    None
+}
+
+// Handles "a b", "a, b" and Org's ":a:b:"
+fn split_tags(tags: &str) -> Vec<String> {
+    tags.split(|c: char| c == ':' || c == ',' || c.is_whitespace())
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn tags_from_list_or_string<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Tags {
+        List(Vec<String>),
+        Text(String),
+        Nothing(()),
+    }
+
+    Ok(match Tags::deserialize(deserializer)? {
+        Tags::List(tags) => tags.into_iter().filter(|tag| !tag.is_empty()).collect(),
+        Tags::Text(tags) => split_tags(&tags),
+        Tags::Nothing(()) => Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rewrite_with(syntax: Syntax, content: &str, changes: &Changes) -> String {
+        let (_, offset) = parse_as(syntax, content).unwrap();
+        rewrite(syntax, content, offset, changes)
+    }
+
+    #[test]
+    fn unchanged_values_keep_their_lines() {
+        let content = "---\ntitle: Old\ntags: [a, b]\nindentifier: '20240101T000000'\n---\nbody\n";
+        let changes = Changes {
+            title: Some("New".to_string()),
+            file_tags: Some(vec!["a".to_string(), "b".to_string()]),
+            identifier: Some("20240101T000000".to_string()),
+        };
+
+        assert_eq!(
+            rewrite_with(Syntax::Yaml, content, &changes),
+            "---\ntitle: New\ntags: [a, b]\nindentifier: '20240101T000000'\n---\nbody\n"
+        );
+    }
+
+    #[test]
+    fn nothing_changed_is_byte_for_byte() {
+        let content = "+++\ntitle = 'Old'\ntags = [ \"a\" ]\nidentifier = '20240101T000000'\n+++\n";
+        let changes = Changes {
+            title: Some("Old".to_string()),
+            file_tags: Some(vec!["a".to_string()]),
+            identifier: Some("20240101T000000".to_string()),
+        };
+
+        assert_eq!(rewrite_with(Syntax::Toml, content, &changes), content);
+    }
+
+    fn sample() -> FrontMatter {
+        FrontMatter {
+            title: "A \"quoted\" title: with colon".to_string(),
+            date: "2024-03-22 Fri 13:18".to_string(),
+            file_tags: vec!["rust".to_string(), "notes".to_string()],
+            indentifier: "20240322T131856".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn assert_round_trip(syntax: Syntax, written: &str, date: &str) {
+        let body = "\nThe body.\n";
+        let content = format!("{}{}", written, body);
+        let (fm, offset) = parse_as(syntax, &content).unwrap();
+        let expected = sample();
+
+        assert_eq!(fm.title, expected.title);
+        assert_eq!(fm.date, date);
+        assert_eq!(fm.file_tags, expected.file_tags);
+        assert_eq!(fm.indentifier, expected.indentifier);
+        assert_eq!(content[offset..].trim_start_matches('\n'), "The body.\n");
+    }
+
+    #[test]
+    fn yaml_round_trip() {
+        let written = to_yaml_front_matter(sample()).unwrap();
+        assert_round_trip(Syntax::Yaml, &written, "2024-03-22 Fri 13:18");
+    }
+
+    #[test]
+    fn toml_round_trip() {
+        let fm = FrontMatter {
+            date: "2024-03-22T13:18:56+01:00".to_string(),
+            ..sample()
+        };
+        let written = to_toml_front_matter(fm);
+
+        assert!(written.contains("\ndate = 2024-03-22T13:18:56+01:00\n"));
+        assert_round_trip(Syntax::Toml, &written, "2024-03-22T13:18:56+01:00");
+    }
+
+    #[test]
+    fn org_round_trip() {
+        let written = to_org_front_matter(sample()) + "\n";
+        assert_round_trip(Syntax::Org, &written, "2024-03-22 Fri 13:18");
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let written = to_text_front_matter(sample());
+        assert_round_trip(Syntax::Text, &written, "2024-03-22 Fri 13:18");
+    }
+
+    #[test]
+    fn typst_round_trip() {
+        let written = to_typst_front_matter(sample());
+        assert_round_trip(Syntax::Typst, &written, "2024-03-22 Fri 13:18");
+    }
+
+    #[test]
+    fn template_answers_never_repeat_a_field() {
+        let fm = FrontMatter {
+            extra: vec![
+                ("title".to_string(), "Shadow".to_string()),
+                ("TAGS".to_string(), "shadow".to_string()),
+                ("author".to_string(), "me".to_string()),
+            ],
+            ..sample()
+        };
+        let written = to_toml_front_matter(fm);
+
+        assert_eq!(written.matches("title = ").count(), 1);
+        assert!(!written.contains("TAGS"));
+        assert!(written.contains("\nauthor = \"me\"\n"));
+    }
+
+    fn rename_changes() -> Changes {
+        Changes {
+            title: Some("New title".to_string()),
+            file_tags: Some(vec!["x".to_string(), "y".to_string()]),
+            identifier: Some("20240322T131856".to_string()),
+        }
+    }
+
+    #[test]
+    fn rewrite_keeps_unknown_fields_and_body() {
+        let cases = [
+            (
+                Syntax::Yaml,
+                "---\ntitle: Old\nauthor: me\ntags: [a]\nidentifier: \"20240322T131856\"\n---\n\n# Body\ntitle: not front matter\n",
+                "---\ntitle: New title\nauthor: me\ntags: [\"x\", \"y\"]\nidentifier: \"20240322T131856\"\n---\n\n# Body\ntitle: not front matter\n",
+            ),
+            (
+                Syntax::Toml,
+                "+++\ntitle = \"Old\"\ndraft = true\ntags = [\"a\"]\n+++\nBody\n",
+                "+++\ntitle = \"New title\"\ndraft = true\ntags = [\"x\", \"y\"]\n+++\nBody\n",
+            ),
+            (
+                Syntax::Org,
+                "#+title:      Old\n#+author: me\n#+filetags:   :a:\n\n* Body\n",
+                "#+title:      New title\n#+author: me\n#+filetags:   :x:y:\n\n* Body\n",
+            ),
+            (
+                Syntax::Text,
+                "title:      Old\nauthor:     me\ntags:       a\n---------------------------\n\nBody\n",
+                "title:      New title\nauthor:     me\ntags:       x  y\n---------------------------\n\nBody\n",
+            ),
+            (
+                Syntax::Typst,
+                "#set document(\n  title: \"Old\",\n  author: \"me\",\n  keywords: (\"a\",),\n)\n// identifier: 20240322T131856\n\nBody\n",
+                "#set document(\n  title: \"New title\",\n  author: \"me\",\n  keywords: (\"x\", \"y\"),\n)\n// identifier: 20240322T131856\n\nBody\n",
+            ),
+        ];
+
+        for (syntax, content, expected) in cases {
+            assert_eq!(rewrite_with(syntax, content, &rename_changes()), expected, "{:?}", syntax);
+        }
+    }
+
+    #[test]
+    fn yaml_block_lists_are_replaced_whole() {
+        let content = "---\ntitle: Old\ntags:\n  - a\n  - b\nauthor: me\n---\nBody\n";
+
+        assert_eq!(
+            rewrite_with(Syntax::Yaml, content, &rename_changes()),
+            "---\ntitle: New title\ntags:\n  - x\n  - y\nauthor: me\n---\nBody\n"
+        );
+        assert_eq!(parse_as(Syntax::Yaml, content).unwrap().0.file_tags, ["a", "b"]);
+    }
+
+    #[test]
+    fn multi_line_toml_arrays_are_replaced_whole() {
+        let content = "+++\ntitle = \"Old\"\ntags = [\n  \"a\",\n  \"b\",\n]\nauthor = \"me\"\n+++\nBody\n";
+
+        assert_eq!(
+            rewrite_with(Syntax::Toml, content, &rename_changes()),
+            "+++\ntitle = \"New title\"\ntags = [\"x\", \"y\"]\nauthor = \"me\"\n+++\nBody\n"
+        );
+        assert_eq!(parse_as(Syntax::Toml, content).unwrap().0.file_tags, ["a", "b"]);
+    }
+
+    #[test]
+    fn denote_compatible_round_trip() {
+        let cases = [
+            (types::FileType::Markdown, Syntax::Yaml, "2024-03-22T13:18:56+01:00"),
+            (types::FileType::MarkdownToml, Syntax::Toml, "2024-03-22T13:18:56+01:00"),
+            (types::FileType::Org, Syntax::Org, "[2024-03-22 Fri 13:18]"),
+            (types::FileType::Text, Syntax::Text, "2024-03-22"),
+        ];
+
+        for (file_type, syntax, date) in cases {
+            let fm = FrontMatter {
+                date: date.to_string(),
+                ..sample()
+            };
+            let written = to_denote_front_matter(file_type, fm);
+
+            assert!(written.ends_with("\n\n"), "{:?}", file_type);
+            assert_round_trip(syntax, written.trim_end_matches('\n'), date);
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    env,
+    fmt::{self, Display},
+    fs,
+    io::Error,
+    path::{Path, PathBuf},
+};
+
+use chrono::Local;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+// --- Operation history ---
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Action {
+    Write,
+    // Renames and moves, the front matter may have been rewritten too
+    Rename,
+    Copy,
+}
+
+// One file an operation touched
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Change {
+    // Unset for new notes
+    #[serde(default)]
+    pub from: Option<PathBuf>,
+    pub to: PathBuf,
+    // The content before the front matter was rewritten to match a new name
+    #[serde(default)]
+    pub old_content: Option<String>,
+    // Of the file right after the operation, so later edits are noticed
+    pub fingerprint: String,
+}
+
+impl Change {
+    pub fn new(from: Option<&Path>, to: &Path, old_content: Option<String>) -> std::io::Result<Self> {
+        Ok(Change {
+            from: from.map(Path::to_path_buf),
+            to: to.to_path_buf(),
+            old_content,
+            fingerprint: fingerprint(to)?,
+        })
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let file_name = |path: &Path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or("<invalid>")
+                .to_string()
+        };
+
+        match &self.from {
+            Some(from) => write!(
+                f,
+                "{} -> {}",
+                file_name(from).red(),
+                file_name(&self.to).green()
+            ),
+            None => write!(f, "{}", file_name(&self.to).green()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Entry {
+    pub time: String,
+    pub action: Action,
+    // A single file, or every file of a batch rename, undone together
+    pub changes: Vec<Change>,
+    #[serde(default)]
+    pub undone: bool,
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = match self.action {
+            Action::Write => "Wrote",
+            Action::Rename => "Renamed",
+            Action::Copy => "Copied",
+        };
+
+        write!(f, "{} {} ", self.time.cyan(), action)?;
+
+        match self.changes.as_slice() {
+            [change] => write!(f, "{}", change),
+            changes => write!(f, "{} files", changes.len()),
+        }
+    }
+}
+
+// Older operations are dropped, so renamed notes' old content doesn't pile up forever
+const MAX_OPERATIONS: usize = 100;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct History {
+    #[serde(default)]
+    operations: Vec<Entry>,
+}
+
+fn get_path() -> PathBuf {
+    let home: String = env::var("HOME").unwrap_or_else(|_| ".".to_string());
+
+    PathBuf::from(format!("{}/.decoy/history.toml", home))
+}
+
+pub(crate) fn record(action: Action, changes: Vec<Change>) -> std::io::Result<()> {
+    // A batch that failed on its first file changed nothing
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    let mut operations = load()?;
+    operations.push(Entry {
+        time: Local::now().format("%F %T").to_string(),
+        action,
+        changes,
+        undone: false,
+    });
+
+    let overflow = operations.len().saturating_sub(MAX_OPERATIONS);
+    operations.drain(..overflow);
+
+    save(operations)
+}
+
+// Oldest first
+pub(crate) fn load() -> std::io::Result<Vec<Entry>> {
+    let path = get_path();
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let history: History = toml::from_str(&fs::read_to_string(path)?).map_err(Error::other)?;
+
+    Ok(history.operations)
+}
+
+pub(crate) fn mark_undone(index: usize) -> std::io::Result<()> {
+    let mut operations = load()?;
+
+    if let Some(entry) = operations.get_mut(index) {
+        entry.undone = true;
+    }
+
+    save(operations)
+}
+
+fn save(operations: Vec<Entry>) -> std::io::Result<()> {
+    let toml = toml::to_string(&History { operations }).map_err(Error::other)?;
+
+    let path = get_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(path, toml)
+}
+
+// FNV-1a over the file's bytes, enough to tell whether it was edited
+pub(crate) fn fingerprint(path: &Path) -> std::io::Result<String> {
+    let hash = fs::read(path)?
+        .iter()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+        });
+
+    Ok(format!("{:016x}", hash))
+}
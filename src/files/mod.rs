@@ -2,3 +2,6 @@ pub(crate) mod types;
 pub(crate) mod operations;
 pub(crate) mod note;
 pub(crate) mod frontmatter;
+pub(crate) mod name;
+pub(crate) mod draft;
+pub(crate) mod history;
@@ -0,0 +1,418 @@
+use std::fmt::{self, Display};
+
+use chrono::NaiveDateTime;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+// Compact ISO 8601, e.g. 20240322T131856
+pub(crate) const ID_FORMAT: &str = "%Y%m%dT%H%M%S";
+
+// The identifier only needs a delimiter when it is not the first component
+const IDENTIFIER: &str = "@@";
+const SIGNATURE: &str = "==";
+const TITLE: &str = "--";
+const KEYWORDS: &str = "__";
+
+// --- File name components ---
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Component {
+    Identifier,
+    Signature,
+    Title,
+    Keywords,
+}
+
+pub(crate) const DEFAULT_ORDER: [Component; 4] = [
+    Component::Identifier,
+    Component::Signature,
+    Component::Title,
+    Component::Keywords,
+];
+
+// Drops duplicates and appends anything missing in the default order
+pub(crate) fn normalise_order(order: &[Component]) -> [Component; 4] {
+    let mut normalised = Vec::with_capacity(4);
+
+    for component in order.iter().chain(DEFAULT_ORDER.iter()) {
+        if !normalised.contains(component) {
+            normalised.push(*component);
+        }
+    }
+
+    [normalised[0], normalised[1], normalised[2], normalised[3]]
+}
+
+// --- Denote file names ---
+// identifier==signature--title__keyword1_keyword2.ext
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DenoteName {
+    pub identifier: Option<String>,
+    pub signature: Option<String>,
+    pub title: Option<String>,
+    pub keywords: Vec<String>,
+    // Includes the leading '.', empty when the file has no extension
+    pub extension: String,
+    // The order components are written in, parsed names keep their own
+    pub order: [Component; 4],
+}
+
+impl Default for DenoteName {
+    fn default() -> Self {
+        Self {
+            identifier: None,
+            signature: None,
+            title: None,
+            keywords: Vec::new(),
+            extension: String::new(),
+            order: DEFAULT_ORDER,
+        }
+    }
+}
+
+impl DenoteName {
+    // Returns None for file names that are not in the Denote format
+    pub fn parse(file_name: &str) -> Option<Self> {
+        let (head, components) = split_components(file_name);
+
+        // The extension hangs off whichever part comes last
+        let (head, mut extension) = match components.is_empty() {
+            true => split_extension(head),
+            false => (head, ""),
+        };
+
+        let mut name = DenoteName::default();
+        let mut seen: Vec<Component> = Vec::with_capacity(4);
+
+        // Anything before the first delimiter can only be an identifier
+        match head {
+            "" => {}
+            id if is_identifier(id) => {
+                name.identifier = Some(id.to_string());
+                seen.push(Component::Identifier);
+            }
+            _ => return None,
+        }
+
+        for (i, (delimiter, value)) in components.iter().enumerate() {
+            let value = if i + 1 == components.len() {
+                let (value, ext) = split_extension(value);
+                extension = ext;
+                value
+            } else {
+                value
+            };
+
+            if value.is_empty() {
+                return None;
+            }
+
+            let component = match *delimiter {
+                // A leading identifier is written without its delimiter
+                IDENTIFIER if seen.is_empty() => return None,
+                IDENTIFIER if is_identifier(value) => {
+                    name.identifier = Some(value.to_string());
+                    Component::Identifier
+                }
+                IDENTIFIER => return None,
+                SIGNATURE => {
+                    name.signature = Some(value.to_string());
+                    Component::Signature
+                }
+                TITLE => {
+                    name.title = Some(value.to_string());
+                    Component::Title
+                }
+                _ => {
+                    name.keywords = value.split('_').map(str::to_string).collect();
+                    if name.keywords.iter().any(String::is_empty) {
+                        return None;
+                    }
+                    Component::Keywords
+                }
+            };
+
+            // Each component may only appear once
+            if seen.contains(&component) {
+                return None;
+            }
+            seen.push(component);
+        }
+
+        if seen.is_empty() {
+            return None;
+        }
+
+        name.extension = extension.to_string();
+        name.order = normalise_order(&seen);
+        Some(name)
+    }
+
+    // The present components in written order, as (component, delimiter, value)
+    pub fn components(&self) -> Vec<(Component, &'static str, String)> {
+        let mut parts: Vec<(Component, &'static str, String)> = Vec::with_capacity(4);
+
+        for component in self.order {
+            let (delimiter, value) = match component {
+                Component::Identifier => (IDENTIFIER, self.identifier.clone()),
+                Component::Signature => (SIGNATURE, self.signature.clone()),
+                Component::Title => (TITLE, self.title.clone()),
+                Component::Keywords => (
+                    KEYWORDS,
+                    Some(self.keywords.join("_")).filter(|kws| !kws.is_empty()),
+                ),
+            };
+
+            if let Some(value) = value {
+                // A leading identifier is written without its delimiter
+                let delimiter = match (component, parts.is_empty()) {
+                    (Component::Identifier, true) => "",
+                    _ => delimiter,
+                };
+                parts.push((component, delimiter, value));
+            }
+        }
+
+        parts
+    }
+}
+
+impl Display for DenoteName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (_, delimiter, value) in self.components() {
+            write!(f, "{}{}", delimiter, value)?;
+        }
+
+        write!(f, "{}", self.extension)
+    }
+}
+
+// --- Slugs ---
+// Most filesystems cap a single file name at 255 bytes
+pub(crate) const MAX_FILENAME_BYTES: usize = 255;
+
+// Punctuation Denote drops outright rather than turning into a hyphen
+const EXCLUDED_PUNCTUATION: &str = "[]{}!@#$%^&*()+'\"?,.|;:~`‘’“”/=<>\\";
+
+// "What's Up? Café/Bar_notes" -> "whats-up-cafebar-notes"
+pub(crate) fn slugify_title(title: &str) -> String {
+    let mut slug = String::with_capacity(title.len());
+
+    for c in title.chars().flat_map(char::to_lowercase) {
+        match c {
+            c if c.is_ascii_alphanumeric() => slug.push(c),
+            c if c.is_whitespace() || c == '-' || c == '_' => slug.push('-'),
+            c if EXCLUDED_PUNCTUATION.contains(c) => {}
+            c => slug.push_str(transliterate(c)),
+        }
+    }
+
+    collapse_hyphens(&slug)
+}
+
+// Keywords are single words, so hyphens and underscores are dropped too
+pub(crate) fn slugify_keyword(keyword: &str) -> String {
+    keyword
+        .chars()
+        .flat_map(char::to_lowercase)
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_string(),
+            false => transliterate(c).to_string(),
+        })
+        .collect()
+}
+
+// Signatures keep '=' so sequences like 1=2 survive
+pub(crate) fn slugify_signature(signature: &str) -> String {
+    signature
+        .chars()
+        .flat_map(char::to_lowercase)
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '=')
+        .collect::<String>()
+        .split('=')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("=")
+}
+
+fn collapse_hyphens(slug: &str) -> String {
+    slug.split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+// Common Latin letters, everything else non-ASCII is dropped
+fn transliterate(c: char) -> &'static str {
+    match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'æ' => "ae",
+        'ç' | 'ć' | 'č' => "c",
+        'ď' | 'đ' | 'ð' => "d",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ė' | 'ę' | 'ě' => "e",
+        'ğ' => "g",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'į' | 'ı' => "i",
+        'ł' | 'ľ' => "l",
+        'ñ' | 'ń' | 'ň' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => "o",
+        'œ' => "oe",
+        'ř' => "r",
+        'ś' | 'š' | 'ş' => "s",
+        'ß' => "ss",
+        'ť' | 'ţ' => "t",
+        'þ' => "th",
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' | 'ų' => "u",
+        'ý' | 'ÿ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        _ => "",
+    }
+}
+
+// Drops trailing title words until the file name fits, returns true if anything was cut
+pub(crate) fn fit_filename(name: &mut DenoteName) -> bool {
+    let mut shortened = false;
+
+    while name.to_string().len() > MAX_FILENAME_BYTES {
+        let overflow = name.to_string().len() - MAX_FILENAME_BYTES;
+        let Some(title) = name.title.as_mut() else {
+            break;
+        };
+
+        match title.rsplit_once('-') {
+            Some((rest, _)) => *title = rest.to_string(),
+            // A single huge word is cut to whatever room is left
+            None => {
+                title.truncate(title.len().saturating_sub(overflow));
+                if title.is_empty() {
+                    name.title = None;
+                }
+            }
+        }
+        shortened = true;
+    }
+
+    shortened
+}
+
+// Fits the file name and says so when the title had to be cut, on stderr so scripts only read the path
+pub(crate) fn warn_if_shortened(name: &mut DenoteName) {
+    if fit_filename(name) {
+        eprintln!(
+            "{} File name is longer than {} bytes, shortened the title to: {}",
+            "#".red(),
+            MAX_FILENAME_BYTES,
+            name.title.as_deref().unwrap_or_default().italic().magenta(),
+        );
+    }
+}
+
+// --- Parsing helpers ---
+pub(crate) fn is_identifier(id: &str) -> bool {
+    NaiveDateTime::parse_from_str(id, ID_FORMAT).is_ok()
+}
+
+// Splits "head==a--b__c" into ("head", [("==", "a"), ("--", "b"), ("__", "c")])
+fn split_components(stem: &str) -> (&str, Vec<(&str, &str)>) {
+    let mut marks: Vec<(usize, &str)> = [IDENTIFIER, SIGNATURE, TITLE, KEYWORDS]
+        .iter()
+        .flat_map(|delimiter| stem.match_indices(delimiter))
+        .collect();
+    marks.sort();
+
+    let head = marks.first().map_or(stem, |(start, _)| &stem[..*start]);
+
+    let components = marks
+        .iter()
+        .enumerate()
+        .map(|(i, (start, delimiter))| {
+            let end = marks.get(i + 1).map_or(stem.len(), |(next, _)| *next);
+            (*delimiter, &stem[start + delimiter.len()..end])
+        })
+        .collect();
+
+    (head, components)
+}
+
+// The extension starts at the first '.', so "kw.tar.gz" keeps ".tar.gz"
+fn split_extension(part: &str) -> (&str, &str) {
+    match part.find('.') {
+        Some(dot) => (&part[..dot], &part[dot..]),
+        None => (part, ""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(file_name: &str) -> DenoteName {
+        let name = DenoteName::parse(file_name).unwrap();
+        assert_eq!(name.to_string(), file_name);
+        name
+    }
+
+    #[test]
+    fn identifier_only() {
+        let name = round_trip("20240322T131856.md");
+
+        assert_eq!(name.identifier.as_deref(), Some("20240322T131856"));
+        assert_eq!(name.title, None);
+        assert!(name.keywords.is_empty());
+        assert_eq!(name.extension, ".md");
+    }
+
+    #[test]
+    fn full_name_with_signature() {
+        let name = round_trip("20240322T131856==1a2--a-title__rust_notes.org");
+
+        assert_eq!(name.signature.as_deref(), Some("1a2"));
+        assert_eq!(name.title.as_deref(), Some("a-title"));
+        assert_eq!(name.keywords, ["rust", "notes"]);
+        assert_eq!(name.order, DEFAULT_ORDER);
+    }
+
+    #[test]
+    fn title_first_keeps_its_order() {
+        let name = round_trip("--a-title@@20240322T131856__kw.md");
+
+        assert_eq!(name.identifier.as_deref(), Some("20240322T131856"));
+        assert_eq!(name.order[..2], [Component::Title, Component::Identifier]);
+    }
+
+    #[test]
+    fn multi_dot_extension() {
+        let name = round_trip("20240322T131856--backup__archive.tar.gz");
+
+        assert_eq!(name.keywords, ["archive"]);
+        assert_eq!(name.extension, ".tar.gz");
+    }
+
+    #[test]
+    fn rejects_non_denote_names() {
+        for file_name in [
+            "___",
+            "meeting-notes.md",
+            "README.md",
+            "20240322T131856--one--two.md",
+            "20240322T131856__a__b.md",
+            "not-an-id--title.md",
+            "@@20240322T131856--title.md",
+            "20240322T131856--.md",
+        ] {
+            assert_eq!(DenoteName::parse(file_name), None, "{}", file_name);
+        }
+    }
+
+    #[test]
+    fn formats_a_built_name() {
+        let name = DenoteName {
+            identifier: Some("20240322T131856".to_string()),
+            signature: Some("1=2".to_string()),
+            title: Some(slugify_title("What's Up? Café/Bar")),
+            keywords: vec![slugify_keyword("Rust-Lang")],
+            extension: ".md".to_string(),
+            order: DEFAULT_ORDER,
+        };
+
+        assert_eq!(name.to_string(), "20240322T131856==1=2--whats-up-cafebar__rustlang.md");
+    }
+}
@@ -1,95 +1,252 @@
 use std::{fmt::{self, Display}, fs, path::{Path, PathBuf}};
 
-use chrono::{NaiveDate, NaiveDateTime};
+use chrono::{NaiveDate, NaiveDateTime, TimeDelta};
 use colored::Colorize;
 use inquire::InquireError;
 use itertools::Itertools;
 
+use crate::files::{
+    frontmatter::FrontMatter,
+    name::{self, Component, DenoteName},
+};
+
 // --- Notes ---
 #[derive(Debug, Clone)]
-pub(crate) struct Note(pub PathBuf);
+pub(crate) struct Note {
+    pub path: PathBuf,
+    // None when the file name is not in the Denote format
+    pub name: Option<DenoteName>,
+    // Only set when more than one silo is configured
+    pub silo: Option<String>,
+}
+
+impl Note {
+    pub fn new(path: PathBuf) -> Self {
+        let name = path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .and_then(DenoteName::parse);
+
+        Self { path, name, silo: None }
+    }
+
+    pub fn in_silo(self, silo: Option<String>) -> Self {
+        Self { silo, ..self }
+    }
+}
 
 impl Display for Note {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let stem = self.0.file_name().and_then(|s| s.to_str());
-
-        if let Some(filename) = stem {
-            let (ident_opt, tail) = filename.split_once("--").map_or((None, filename), |(id, t)| (Some(id), t));
-            let (name, raw_kws) = tail.split_once("__").unwrap_or((tail, ""));
+        if let Some(silo) = &self.silo {
+            write!(f, "{} ", format!("[{}]", silo).blue())?;
+        }
 
-            let (kws_part, ext_part) = raw_kws
-                .split_once('.')
-                .map(|(kws, ext)| (kws, Some(ext)))
-                .unwrap_or((raw_kws, None));
+        match &self.name {
+            Some(name) => {
+                let styled = name
+                    .components()
+                    .into_iter()
+                    .map(|(component, delimiter, value)| match component {
+                        Component::Identifier => format!("{}{}", delimiter, value.cyan()),
+                        Component::Signature => format!("{}{}", delimiter, value.green()),
+                        Component::Title => format!("{}{}", delimiter, value),
+                        Component::Keywords => format!(
+                            "{}{}",
+                            delimiter,
+                            value.split('_').map(|k| format!("{}", k.yellow())).join("_")
+                        ),
+                    })
+                    .join("");
 
-            let styled_kws = kws_part
-                .split('_')
-                .filter(|k| !k.is_empty())
-                .map(|k| format!("{}", k.yellow()))
-                .collect::<Vec<_>>()
-                .join("_");
-
-            let styled_ext = ext_part.map(|ext| format!(".{}", ext.clear())).unwrap_or_default();
-            let separator = if !styled_kws.is_empty() { "__" } else { "" };
-
-            match ident_opt {
-                Some(ident) => writeln!(
-                    f,
-                    "{}--{}{}{}{}",
-                    ident.cyan(),
-                    name,
-                    separator,
-                    styled_kws,
-                    styled_ext
-                ),
-                None => writeln!(
-                    f,
-                    "{}{}{}{}",
-                    name,
-                    separator,
-                    styled_kws,
-                    styled_ext
-                ),
+                writeln!(f, "{}{}", styled, name.extension)
             }
-        } else {
-            writeln!(f, "{:?}", self.0)
+            None => match self.path.file_name().and_then(|s| s.to_str()) {
+                Some(filename) => writeln!(f, "{}", filename),
+                None => writeln!(f, "{:?}", self.path),
+            },
         }
     }
 }
 
 // --- Loading ---
-pub(crate) fn load(path: &Path) -> Result<Vec<Note>, InquireError> {
+// Walks the note directory, skipping hidden and excluded entries
+// Returns the denote notes and, separately, every other file so they can still be renamed
+pub(crate) fn load(path: &Path, exclude: &[String]) -> Result<(Vec<Note>, Vec<Note>), InquireError> {
     let mut notes: Vec<Note> = Vec::new();
+    let mut others: Vec<Note> = Vec::new();
+    let mut dirs: Vec<PathBuf> = vec![path.to_path_buf()];
 
-    for entry in fs::read_dir(path)? {
-        let dir = entry?;
-        notes.push(Note(dir.path()));
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let entry_path = entry.path();
+
+            if is_ignored(path, &entry_path, exclude) {
+                continue;
+            }
+
+            // Symlinked folders are not followed, so loops are impossible
+            if entry.file_type()?.is_dir() {
+                dirs.push(entry_path);
+                continue;
+            }
+
+            let note = Note::new(entry_path);
+            match note.name {
+                Some(_) => notes.push(note),
+                None => others.push(note),
+            }
+        }
+    }
+
+    Ok((notes, others))
+}
+
+fn is_ignored(root: &Path, path: &Path, exclude: &[String]) -> bool {
+    let file_name = path.file_name().and_then(|s| s.to_str()).unwrap_or_default();
+
+    // .git, .obsidian, editor swap files, ...
+    if file_name.starts_with('.') {
+        return true;
+    }
+
+    let relative = path
+        .strip_prefix(root)
+        .ok()
+        .and_then(|rel| rel.to_str())
+        .unwrap_or_default();
+
+    exclude
+        .iter()
+        .any(|pattern| wildcard_match(pattern, file_name) || wildcard_match(pattern, relative))
+}
+
+// Shell style matching where '*' is any run of characters and '?' any single one
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.trim_end_matches('/').chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Let the last '*' swallow one more character
+                Some((star, matched)) => {
+                    backtrack = Some((star, matched + 1));
+                    p = star + 1;
+                    t = matched + 1;
+                }
+                None => return false,
+            },
+        }
     }
 
-    Ok(notes)
+    pattern[p..].iter().all(|c| *c == '*')
 }
 
 // --- Parsing ---
 pub(crate) fn parse_all_keywords(notes: &[Note]) -> Vec<String> {
     notes
         .iter()
-        .filter_map(|p| p.0.file_stem()?.to_str())
-        .flat_map(|name| name.split("__").skip(1).flat_map(|seg| seg.split('_')))
-        .map(|tag| tag.to_string())
-        .filter(|tag| !tag.is_empty())
+        .filter_map(|note| note.name.as_ref())
+        .flat_map(|name| name.keywords.iter())
         .unique()
+        .cloned()
+        .collect()
+}
+
+pub(crate) fn parse_all_signatures(notes: &[Note]) -> Vec<String> {
+    notes
+        .iter()
+        .filter_map(|note| note.name.as_ref()?.signature.clone())
+        .unique()
+        .sorted()
         .collect()
 }
 
 pub(crate) fn parse_date(note: &Note) -> Option<NaiveDate> {
-    let filename = note.0.file_stem()?.to_str()?;
-    let (ident, _) = filename.split_once("--")?;
+    let ident = note.name.as_ref()?.identifier.as_ref()?;
 
-    NaiveDateTime::parse_from_str(ident, "%Y%m%dT%H%M%S")
+    NaiveDateTime::parse_from_str(ident, name::ID_FORMAT)
         .ok()
         .map(|dt| dt.date())
 }
 
+// --- Identifiers ---
+pub(crate) fn identifier_taken(notes: &[Note], identifier: &str) -> bool {
+    notes
+        .iter()
+        .filter_map(|note| note.name.as_ref()?.identifier.as_deref())
+        .any(|id| id == identifier)
+}
+
+// Steps the identifier forward a second at a time until neither it nor the file name is taken
+pub(crate) fn ensure_unique(notes: &[Note], dir: &Path, name: &mut DenoteName) {
+    let Some(mut time) = name
+        .identifier
+        .as_deref()
+        .and_then(|id| NaiveDateTime::parse_from_str(id, name::ID_FORMAT).ok())
+    else {
+        return;
+    };
+
+    loop {
+        let identifier = time.format(name::ID_FORMAT).to_string();
+        name.identifier = Some(identifier.clone());
+
+        if !identifier_taken(notes, &identifier) && !dir.join(name.to_string()).exists() {
+            return;
+        }
+
+        time += TimeDelta::seconds(1);
+    }
+}
+
+// --- Front matter ---
+// The file name the front matter describes, the identifier, signature and order in the file name win
+pub(crate) fn name_from_front_matter(note: &Note, fm: &FrontMatter) -> Option<DenoteName> {
+    let old = note.name.as_ref()?;
+
+    let identifier = old
+        .identifier
+        .clone()
+        .or_else(|| Some(fm.indentifier.clone()).filter(|id| name::is_identifier(id)));
+
+    let title = Some(name::slugify_title(&fm.title))
+        .filter(|title| !title.is_empty())
+        .or_else(|| old.title.clone());
+
+    let keywords = fm
+        .file_tags
+        .iter()
+        .map(|kw| name::slugify_keyword(kw))
+        .filter(|kw| !kw.is_empty())
+        .unique()
+        .collect();
+
+    let mut new = DenoteName {
+        identifier,
+        signature: old.signature.clone(),
+        title,
+        keywords,
+        extension: old.extension.clone(),
+        order: old.order,
+    };
+    name::fit_filename(&mut new);
+
+    Some(new)
+}
+
 // --- Note manipulation ---
 pub fn search_by_date(notes: &[Note], date: NaiveDate) -> Vec<Note> {
     notes.iter()
@@ -103,6 +260,162 @@ pub fn search_by_date(notes: &[Note], date: NaiveDate) -> Vec<Note> {
         .collect()
 }
 
+pub fn search_by_signature(notes: &[Note], signature: &str) -> Vec<Note> {
+    notes
+        .iter()
+        .filter(|note| signature_of(note).is_some_and(|sig| signature_matches(sig, signature)))
+        .cloned()
+        .sorted_by_key(|note| signature_of(note).map(sequence_levels))
+        .collect()
+}
+
+// A prefix match that never splits a number, so 1 finds 1a and 1=2 but not 10
+fn signature_matches(signature: &str, prefix: &str) -> bool {
+    match signature.strip_prefix(prefix) {
+        Some(rest) => !(prefix.ends_with(|c: char| c.is_ascii_digit())
+            && rest.starts_with(|c: char| c.is_ascii_digit())),
+        None => false,
+    }
+}
+
+pub(crate) fn search_by_silo(notes: &[Note], silo: &str) -> Vec<Note> {
+    notes
+        .iter()
+        .filter(|note| note.silo.as_deref() == Some(silo))
+        .cloned()
+        .collect()
+}
+
+// --- Sequences ---
+// One level of a signature, numbers sort before letters
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Level {
+    Number(u64),
+    Text(String),
+}
+
+// "1=2b" reads as [1, 2, b], so 1 < 1=1 < 1=2 < 1=10 < 2
+pub(crate) fn sequence_levels(signature: &str) -> Vec<Level> {
+    signature
+        .split('=')
+        .flat_map(|part| {
+            part.chars()
+                .chunk_by(|c| c.is_ascii_digit())
+                .into_iter()
+                .map(|(digits, run)| {
+                    let run: String = run.collect();
+                    match digits {
+                        true => run.parse().map_or(Level::Text(run), Level::Number),
+                        false => Level::Text(run),
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn signature_of(note: &Note) -> Option<&str> {
+    note.name.as_ref()?.signature.as_deref()
+}
+
+// Numeric sequences like 1=1=2, the only kind new children and siblings are numbered in
+pub(crate) fn is_sequence(signature: &str) -> bool {
+    signature
+        .split('=')
+        .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+// Notes with a numeric sequence signature, in hierarchical order
+pub(crate) fn sequence_notes(notes: &[Note]) -> Vec<Note> {
+    notes
+        .iter()
+        .filter(|note| signature_of(note).is_some_and(is_sequence))
+        .cloned()
+        .sorted_by_key(|note| signature_of(note).map(sequence_levels))
+        .collect()
+}
+
+// The next free number under a sequence, or at the top level for None
+fn next_in(notes: &[Note], parent: Option<&str>) -> String {
+    let last = notes
+        .iter()
+        .filter_map(signature_of)
+        .filter(|sig| is_sequence(sig))
+        .filter_map(|sig| {
+            let rest = match parent {
+                Some(parent) => sig.strip_prefix(parent)?.strip_prefix('=')?,
+                None => sig,
+            };
+            rest.parse::<u64>().ok()
+        })
+        .max()
+        .unwrap_or(0);
+
+    match parent {
+        Some(parent) => format!("{}={}", parent, last + 1),
+        None => (last + 1).to_string(),
+    }
+}
+
+// 1=2 -> 1=2=1, or the next number after its existing children
+pub(crate) fn next_child(notes: &[Note], signature: &str) -> String {
+    next_in(notes, Some(signature))
+}
+
+// 1=2 -> 1=3, or the next number after its existing siblings
+pub(crate) fn next_sibling(notes: &[Note], signature: &str) -> String {
+    next_in(notes, signature.rsplit_once('=').map(|(parent, _)| parent))
+}
+
+// Where a new note joins a sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum NewRelative {
+    Child,
+    Sibling,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Relation {
+    Parent,
+    Children,
+    Siblings,
+}
+
+// The notes one level above, one level below or beside a note, in hierarchical order
+pub(crate) fn search_by_relation(notes: &[Note], note: &Note, relation: Relation) -> Vec<Note> {
+    // Only numeric sequences, like next_in, so 1a is never read as a child of 1
+    let sequence_levels_of = |note: &Note| {
+        signature_of(note)
+            .filter(|sig| is_sequence(sig))
+            .map(sequence_levels)
+    };
+
+    let Some(levels) = sequence_levels_of(note) else {
+        return Vec::new();
+    };
+
+    notes
+        .iter()
+        .filter(|other| other.path != note.path)
+        .filter(|other| {
+            let Some(other) = sequence_levels_of(other) else {
+                return false;
+            };
+
+            match relation {
+                Relation::Parent => other.len() + 1 == levels.len() && levels.starts_with(&other),
+                Relation::Children => other.len() == levels.len() + 1 && other.starts_with(&levels),
+                Relation::Siblings => {
+                    other.len() == levels.len()
+                        && other[..other.len() - 1] == levels[..levels.len() - 1]
+                }
+            }
+        })
+        .cloned()
+        .sorted_by_key(|note| signature_of(note).map(sequence_levels))
+        .collect()
+}
+
 // --- File manipulation ---
 pub fn search_by_keywords(notes: &[Note], keywords: Vec<String>) -> Vec<Note> {
     if keywords.is_empty() {
@@ -111,23 +424,61 @@ pub fn search_by_keywords(notes: &[Note], keywords: Vec<String>) -> Vec<Note> {
         notes
             .iter()
             .filter(|note| {
-                let tags = note
-                    .0
-                    .file_stem()
-                    .and_then(|os_str| os_str.to_str())
-                    .map(|name| {
-                        name.split("__")
-                            .skip(1)
-                            .flat_map(|seg| seg.split('_'))
-                            .map(|tag| tag.to_string())
-                            .unique()
-                            .collect::<Vec<_>>()
-                    })
-                    .unwrap_or_default();
-
-                tags.iter().any(|tag| keywords.contains(tag))
+                note.name
+                    .as_ref()
+                    .is_some_and(|name| name.keywords.iter().any(|tag| keywords.contains(tag)))
             })
             .cloned()
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notes(signatures: &[&str]) -> Vec<Note> {
+        signatures
+            .iter()
+            .enumerate()
+            .map(|(i, sig)| Note::new(PathBuf::from(format!("20240101T00000{}=={}--note.md", i, sig))))
+            .collect()
+    }
+
+    fn signatures(notes: &[Note]) -> Vec<&str> {
+        notes.iter().filter_map(signature_of).collect()
+    }
+
+    #[test]
+    fn front_matter_renames_keep_the_name_order() {
+        let note = Note::new(PathBuf::from("--old-title@@20240101T000000__a.md"));
+        let fm = FrontMatter {
+            title: "Old title".to_string(),
+            file_tags: vec!["a".to_string()],
+            ..Default::default()
+        };
+
+        let name = name_from_front_matter(&note, &fm).unwrap();
+        assert_eq!(Some(&name), note.name.as_ref());
+    }
+
+    #[test]
+    fn relatives_are_numeric_sequences_only() {
+        let notes = notes(&["1", "1=1", "1=2", "1a", "1b", "2", "draft"]);
+
+        assert_eq!(signatures(&search_by_relation(&notes, &notes[0], Relation::Children)), ["1=1", "1=2"]);
+        assert_eq!(signatures(&search_by_relation(&notes, &notes[0], Relation::Siblings)), ["2"]);
+        assert_eq!(signatures(&search_by_relation(&notes, &notes[2], Relation::Parent)), ["1"]);
+        assert!(search_by_relation(&notes, &notes[3], Relation::Parent).is_empty());
+    }
+
+    #[test]
+    fn new_relatives_are_numbered_after_existing_ones() {
+        let notes = notes(&["1", "1=1", "1=2", "1a", "2"]);
+
+        assert_eq!(next_child(&notes, "1"), "1=3");
+        assert_eq!(next_child(&notes, "1=2"), "1=2=1");
+        assert_eq!(next_sibling(&notes, "1=1"), "1=3");
+        assert_eq!(next_sibling(&notes, "1"), "3");
+    }
+}
@@ -1,28 +1,36 @@
 use std::{
     fs::{self, create_dir_all, File, OpenOptions},
-    io::{Error, Write},
-    path::Path,
+    io::{Error, ErrorKind, Write},
+    path::{Path, PathBuf},
     process::Command,
 };
 
+use colored::Colorize;
+
 use crate::{
     ctx,
-    files::{frontmatter, types},
+    files::{frontmatter, history, name, types},
 };
 
 // --- File manipulation ---
 pub fn write_new_note(
     ctx: &ctx::Ctx,
+    file_type: types::FileType,
     path: &Path,
     frontmatter: frontmatter::FrontMatter,
+    body: &str,
 ) -> std::io::Result<()> {
-    let fm = match ctx.opts.notes_filetype {
+    let fm = match file_type {
+        file_type if ctx.opts.denote_compatible => {
+            frontmatter::to_denote_front_matter(file_type, frontmatter).into_bytes()
+        }
         types::FileType::Org => frontmatter::to_org_front_matter(frontmatter).into_bytes(),
-        _ => format!(
-            "---\n{}---\n",
-            serde_yaml::to_string(&frontmatter).map_err(Error::other)?
-        )
-        .into_bytes(),
+        types::FileType::Text => frontmatter::to_text_front_matter(frontmatter).into_bytes(),
+        types::FileType::Typst => frontmatter::to_typst_front_matter(frontmatter).into_bytes(),
+        types::FileType::MarkdownToml => frontmatter::to_toml_front_matter(frontmatter).into_bytes(),
+        types::FileType::Markdown => frontmatter::to_yaml_front_matter(frontmatter)
+            .map_err(Error::other)?
+            .into_bytes(),
     };
 
     // Ensure parent directory exists
@@ -30,30 +38,270 @@ pub fn write_new_note(
         create_dir_all(parent)?;
     }
 
+    // Never truncate an existing note
     let mut file: File = OpenOptions::new()
         .write(true)
-        .create(true)
-        .truncate(true)
+        .create_new(true)
         .open(path)?;
 
-    // Typst files have no frontmatter
-    if !(ctx.opts.notes_filetype == types::FileType::Typst) {
-        file.write_all(&fm)?;
+    file.write_all(&fm)?;
+
+    // Keep a blank line between the front matter and the template body
+    if !body.is_empty() {
+        let separator = match (fm.ends_with(b"\n\n"), fm.ends_with(b"\n")) {
+            (true, _) => "",
+            (false, true) => "\n",
+            (false, false) => "\n\n",
+        };
+        file.write_all(separator.as_bytes())?;
+        file.write_all(body.as_bytes())?;
+    }
+
+    record_one(history::Action::Write, None, path, None);
+
+    Ok(())
+}
+
+// --- Renaming ---
+#[derive(Debug)]
+pub(crate) struct RenamePlan {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    // The old and new content, when the front matter has to follow the new name
+    pub content: Option<(String, String)>,
+}
+
+// Works out the new path and front matter, nothing is touched until `rename_file`
+pub(crate) fn plan_rename(
+    original: &Path,
+    new_name: &name::DenoteName,
+    title: Option<&str>,
+) -> std::io::Result<RenamePlan> {
+    plan_move(original, original.parent().unwrap_or(Path::new("")), new_name, title)
+}
+
+// Like `plan_rename`, but into another folder
+pub(crate) fn plan_move(
+    original: &Path,
+    dir: &Path,
+    new_name: &name::DenoteName,
+    title: Option<&str>,
+) -> std::io::Result<RenamePlan> {
+    let to = dir.join(new_name.to_string());
+
+    let changes = frontmatter::Changes {
+        title: title.map(str::to_string),
+        file_tags: Some(new_name.keywords.clone()),
+        identifier: new_name.identifier.clone(),
+    };
+
+    let file_type = original
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(types::FileType::from_extension);
+
+    let content = match file_type {
+        Some(file_type) => {
+            let old = fs::read_to_string(original)?;
+            frontmatter::detect(file_type, &old)
+                .map(|(syntax, _, offset)| frontmatter::rewrite(syntax, &old, offset, &changes))
+                .filter(|new| *new != old)
+                .map(|new| (old, new))
+        }
+        None => None,
+    };
+
+    Ok(RenamePlan {
+        from: original.to_path_buf(),
+        to,
+        content,
+    })
+}
+
+pub(crate) fn rename_file(plan: &RenamePlan) -> std::io::Result<()> {
+    rename_files(std::slice::from_ref(plan))
+}
+
+// One operation in the history, so --undo puts every file back together
+pub(crate) fn rename_files(plans: &[RenamePlan]) -> std::io::Result<()> {
+    let mut changes = Vec::with_capacity(plans.len());
+
+    let result = plans.iter().try_for_each(|plan| {
+        refuse_existing(plan)?;
+
+        let content = plan.content.as_ref().map(|(old, new)| (old.as_str(), new.as_str()));
+        move_with_content(&plan.from, &plan.to, content)?;
+
+        let old_content = plan.content.as_ref().map(|(old, _)| old.clone());
+        let change = history::Change::new(Some(&plan.from), &plan.to, old_content);
+        log(change.map(|change| changes.push(change)));
+
+        Ok(())
+    });
+
+    // The files renamed before a failure can still be undone
+    log(history::record(history::Action::Rename, changes));
+
+    result
+}
+
+// Leaves the original where it is
+pub(crate) fn copy_file(plan: &RenamePlan) -> std::io::Result<()> {
+    // fs::copy onto itself would truncate the file
+    if plan.to == plan.from {
+        return Err(Error::new(
+            ErrorKind::AlreadyExists,
+            format!("{:?} is already there", plan.to),
+        ));
+    }
+    refuse_existing(plan)?;
+
+    fs::copy(&plan.from, &plan.to)?;
+
+    // Don't leave a copy behind with the old content
+    if let Some((_, new)) = &plan.content {
+        if let Err(err) = fs::write(&plan.to, new) {
+            let _ = fs::remove_file(&plan.to);
+            return Err(err);
+        }
+    }
+
+    record_one(history::Action::Copy, Some(&plan.from), &plan.to, None);
+
+    Ok(())
+}
+
+// fs::rename can't move a file to another filesystem, so copy it over instead
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    match fs::rename(from, to) {
+        Err(err) if err.kind() == ErrorKind::CrossesDevices => {
+            fs::copy(from, to)?;
+            fs::remove_file(from)
+        }
+        result => result,
+    }
+}
+
+// The content is rewritten in place before the move, so a failed write leaves the file untouched
+// under its old name, and a failed move puts the old content back
+fn move_with_content(from: &Path, to: &Path, content: Option<(&str, &str)>) -> std::io::Result<()> {
+    if let Some((_, new)) = content {
+        fs::write(from, new)?;
+    }
+
+    if let Err(err) = move_file(from, to) {
+        if let Some((old, _)) = content {
+            fs::write(from, old)?;
+        }
+        return Err(err);
     }
 
     Ok(())
 }
 
-pub(crate) fn rename_file(original: &Path, new_stem: &str) -> std::io::Result<()> {
-    let ext = original.extension().and_then(|e| e.to_str());
+// fs::rename and fs::copy would silently replace whatever is already there
+fn refuse_existing(plan: &RenamePlan) -> std::io::Result<()> {
+    match plan.to != plan.from && plan.to.exists() {
+        true => Err(Error::new(
+            ErrorKind::AlreadyExists,
+            format!("{:?} already exists", plan.to),
+        )),
+        false => Ok(()),
+    }
+}
+
+// The file operation already happened, so a history that can't be written is only a warning
+fn log(result: std::io::Result<()>) {
+    if let Err(err) = result {
+        eprintln!("{} Could not record the operation in the history: {}", "#".red(), err);
+    }
+}
+
+// An operation on a single file
+fn record_one(action: history::Action, from: Option<&Path>, to: &Path, old_content: Option<String>) {
+    let change = history::Change::new(from, to, old_content);
+    log(change.and_then(|change| history::record(action, vec![change])));
+}
+
+// --- Undo ---
+// Reverses a recorded operation, refusing when any of its files was edited since
+pub(crate) fn undo(entry: &history::Entry) -> std::io::Result<()> {
+    check_undo(entry)?;
+
+    // Newest first, the reverse of how they were made
+    entry
+        .changes
+        .iter()
+        .rev()
+        .try_for_each(|change| undo_change(entry.action, change))
+}
+
+fn undo_change(action: history::Action, change: &history::Change) -> std::io::Result<()> {
+    match (action, &change.from) {
+        (history::Action::Rename, Some(from)) => {
+            let current = match &change.old_content {
+                Some(_) => Some(fs::read_to_string(&change.to)?),
+                None => None,
+            };
+            let content = current.as_deref().zip(change.old_content.as_deref());
+
+            move_with_content(&change.to, from, content)
+        }
+        (history::Action::Rename, None) => Err(Error::new(
+            ErrorKind::InvalidData,
+            "The rename has no original path",
+        )),
+        // New notes and copies are simply removed again
+        (history::Action::Write | history::Action::Copy, _) => fs::remove_file(&change.to),
+    }
+}
+
+// Whether an operation can still be undone, without touching anything
+pub(crate) fn check_undo(entry: &history::Entry) -> std::io::Result<()> {
+    entry
+        .changes
+        .iter()
+        .try_for_each(|change| check_change(entry.action, change))
+}
 
-    let new_name = match ext {
-        Some(e) => format!("{}.{}", new_stem, e),
-        None => new_stem.to_string(),
+fn check_change(action: history::Action, change: &history::Change) -> std::io::Result<()> {
+    if !change.to.exists() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("{:?} no longer exists", change.to),
+        ));
+    }
+
+    if history::fingerprint(&change.to)? != change.fingerprint {
+        return Err(Error::other(format!(
+            "{:?} has changed since, not undoing",
+            change.to
+        )));
+    }
+
+    // A rename back must not replace another file
+    match (action, &change.from) {
+        (history::Action::Rename, Some(from)) if from != &change.to && from.exists() => Err(
+            Error::new(ErrorKind::AlreadyExists, format!("{:?} already exists", from)),
+        ),
+        _ => Ok(()),
+    }
+}
+
+// None for files without front matter, or that are not text notes at all
+pub(crate) fn read_front_matter(
+    path: &Path,
+) -> std::io::Result<Option<(frontmatter::FrontMatter, usize)>> {
+    let Some(file_type) = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(types::FileType::from_extension)
+    else {
+        return Ok(None);
     };
 
-    let new_path = original.with_file_name(new_name);
-    fs::rename(original, new_path)
+    let content = fs::read_to_string(path)?;
+    Ok(frontmatter::parse(file_type, &content))
 }
 
 pub(crate) fn open_with(ctx: &ctx::Ctx, path: &Path) -> std::io::Result<()> {
@@ -63,7 +311,8 @@ pub(crate) fn open_with(ctx: &ctx::Ctx, path: &Path) -> std::io::Result<()> {
         _ => ctx.opts.editor.text_editor.to_owned(),
     };
 
-    println!("{}", editor);
+    // Kept off stdout, which scripts read the new note's path from
+    eprintln!("{}", editor);
     // env::var("EDITOR").unwrap_or_else(|_| "nano".to_string());
     Command::new(editor).arg(path).status()?;
 
@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum FileType {
     Markdown,
+    // Markdown with +++ fenced TOML front matter, as Hugo and Zola expect
+    MarkdownToml,
     Text,
     Org,
     Typst,
@@ -11,10 +13,33 @@ pub(crate) enum FileType {
 impl AsRef<str> for FileType {
     fn as_ref(&self) -> &str {
         match self {
-            Self::Markdown => ".md",
+            Self::Markdown | Self::MarkdownToml => ".md",
             Self::Text => ".txt",
             Self::Org => ".org",
             Self::Typst => ".typ",
         }
     }
 }
+
+impl FileType {
+    // Takes an extension with or without the leading '.'
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.trim_start_matches('.') {
+            "md" | "markdown" => Some(Self::Markdown),
+            "txt" => Some(Self::Text),
+            "org" => Some(Self::Org),
+            "typ" => Some(Self::Typst),
+            _ => None,
+        }
+    }
+
+    // Takes a config name like MarkdownToml in any case, or an extension
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "markdowntoml" | "markdown-toml" => Some(Self::MarkdownToml),
+            "text" => Some(Self::Text),
+            "typst" => Some(Self::Typst),
+            other => Self::from_extension(other),
+        }
+    }
+}
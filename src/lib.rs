@@ -4,6 +4,7 @@ use inquire::{
     ui::{Attributes, Color, RenderConfig, StyleSheet, Styled},
     InquireError,
 };
+use itertools::Itertools;
 use std::env;
 
 mod prompts;
@@ -36,11 +37,93 @@ pub fn go() -> InquireResult<()> {
     // Run a prompt
     match mode {
         "--new" => {
-            // Create new note
-            let (path, front_matter) = prompts::denote(&ctx)?;
+            // A title on the command line skips the prompts, for scripts and editor plugins
+            let scripted = has_flag(&args, "--title");
 
-            // Write new note with front matter
-            files::operations::write_new_note(&ctx, &path, front_matter)?;
+            // Backdated notes take their date from `--date <when>`, or from a calendar with a bare `--date`
+            let time = match (has_flag(&args, "--date"), flag_value(&args, "--date")) {
+                (true, Some(value)) => Some(files::draft::parse_time(value).ok_or_else(|| {
+                    InquireError::InvalidConfiguration(format!("Could not read date: {}", value))
+                })?),
+                (true, None) if scripted => {
+                    return Err(InquireError::InvalidConfiguration(
+                        "--date needs a value when --title is given".to_string(),
+                    ));
+                }
+                (true, None) => Some(prompts::prompt_date_time()?),
+                (false, _) => None,
+            };
+
+            let (file_type, (path, front_matter, body)) = match scripted {
+                true => {
+                    let draft = draft_from_args(&ctx, &args, time)?;
+                    (draft.file_type, draft.prepare(&ctx))
+                }
+                false => (ctx.opts.notes_filetype, prompts::denote(&ctx, time)?),
+            };
+
+            // Write new note with front matter, followed by the template body
+            files::operations::write_new_note(&ctx, file_type, &path, front_matter, &body)?;
+
+            if scripted {
+                println!("{}", path.display());
+            }
+
+            // Open editor
+            if !has_flag(&args, "--no-edit") {
+                files::operations::open_with(&ctx, &path)?;
+            }
+
+            Ok(())
+        }
+        // Open today's journal entry, creating it when needed
+        "--journal" => {
+            let pick_date = args.iter().any(|arg| arg == "--pick");
+            let (path, new_entry) = prompts::journal(&ctx, pick_date)?;
+
+            if let Some((front_matter, body)) = new_entry {
+                files::operations::write_new_note(
+                    &ctx,
+                    ctx.opts.notes_filetype,
+                    &path,
+                    front_matter,
+                    &body,
+                )?;
+            }
+
+            // Open editor
+            files::operations::open_with(&ctx, &path)?;
+
+            Ok(())
+        }
+        // Continue a sequence from a picked note
+        "--child" | "--sibling" => {
+            let relative = match mode {
+                "--sibling" => files::note::NewRelative::Sibling,
+                _ => files::note::NewRelative::Child,
+            };
+            let (path, front_matter, body) = prompts::sequence_note(&ctx, relative)?;
+
+            files::operations::write_new_note(
+                &ctx,
+                ctx.opts.notes_filetype,
+                &path,
+                front_matter,
+                &body,
+            )?;
+
+            // Open editor
+            files::operations::open_with(&ctx, &path)?;
+
+            Ok(())
+        }
+        "--parent" | "--children" | "--siblings" => {
+            let relation = match mode {
+                "--parent" => files::note::Relation::Parent,
+                "--children" => files::note::Relation::Children,
+                _ => files::note::Relation::Siblings,
+            };
+            let path = prompts::search_notes_by_relation(&ctx, relation)?;
 
             // Open editor
             files::operations::open_with(&ctx, &path)?;
@@ -58,22 +141,54 @@ pub fn go() -> InquireResult<()> {
 }
         // Generate denote for already exisiting file
         "--rename" => {
-            // Search old file
-            let old_path = prompts::search_notes_by_keywords(&ctx)?;
+            // Search old file, files without a denote name can be picked too
+            let old_path = prompts::search_files_to_rename(&ctx)?;
 
-            // Create new note
-            let (new_path, _) = prompts::denote(&ctx)?;
-            let new_name = new_path.file_stem().and_then(|name| name.to_str()).ok_or(
-                InquireError::InvalidConfiguration("Invalid filename".to_string()),
-            )?;
+            // Generate the new name, keeping the identifier
+            let (new_name, title) = prompts::rename(&ctx, &files::note::Note::new(old_path.clone()))?;
+            let plans = vec![files::operations::plan_rename(&old_path, &new_name, Some(&title))?];
+
+            // Rename file, rewriting its front matter to match
+            rename_all(&plans)
+        }
+        // Change one part of a note's name, keeping the rest
+        "--edit-title" | "--edit-keywords" | "--edit-signature" => {
+            let part = match mode {
+                "--edit-title" => files::name::Component::Title,
+                "--edit-keywords" => files::name::Component::Keywords,
+                _ => files::name::Component::Signature,
+            };
+            let plans = prompts::edit(&ctx, part)?;
+
+            rename_all(&plans)
+        }
+        // Apply one change to the names of many notes
+        "--rename-batch" => {
+            let plans = prompts::rename_batch(&ctx)?;
+
+            rename_all(&plans)
+        }
+        // Rebuild file names from the title and tags in the front matter
+        "--rename-fm" => {
+            let plans = prompts::rename_from_front_matter(&ctx)?;
+
+            rename_all(&plans)
+        }
+        // Bring any file into a silo under a Denote name
+        "--import" => {
+            let Some((plan, copy)) = prompts::import(&ctx, args.get(1).map(String::as_str))? else {
+                return Ok(());
+            };
+
+            match copy {
+                true => files::operations::copy_file(&plan)?,
+                false => files::operations::rename_file(&plan)?,
+            }
 
-            // Rename file
-            files::operations::rename_file(&old_path, new_name)?;
             println!(
-                "{} Renamed file: {:?} -> {}",
+                "{} Imported file: {}",
                 ">".magenta(),
-                old_path,
-                new_name.italic().magenta(),
+                plan.to.display().to_string().italic().magenta(),
             );
 
             Ok(())
@@ -87,6 +202,28 @@ pub fn go() -> InquireResult<()> {
 
             Ok(())
         }
+        "--signature" => {
+            // Search notes by signature
+            let path = prompts::search_notes_by_signature(&ctx)?;
+
+            // Open editor
+            files::operations::open_with(&ctx, &path)?;
+
+            Ok(())
+        }
+        // Reverse the last file operation, or one picked with --pick
+        "--undo" => {
+            let Some((index, entry)) = prompts::undo(has_flag(&args, "--pick"))? else {
+                return Ok(());
+            };
+
+            files::operations::undo(&entry)?;
+            files::history::mark_undone(index)?;
+
+            println!("{} Undone: {}", ">".magenta(), entry);
+
+            Ok(())
+        }
         "--config" => {
             // open config
             files::operations::open_with(&ctx, &ctx.opts.opts_path)?;
@@ -99,6 +236,119 @@ pub fn go() -> InquireResult<()> {
     }
 }
 
+// --- Flags ---
+fn has_flag(args: &[String], flag: &str) -> bool {
+    args.iter().any(|arg| arg == flag)
+}
+
+// Every value given for a flag, e.g. both values of `--var a=1 --var b=2`
+fn flag_values<'a>(args: &'a [String], flag: &str) -> Vec<&'a str> {
+    args.windows(2)
+        .filter(|pair| pair[0] == flag && !pair[1].starts_with("--"))
+        .map(|pair| pair[1].as_str())
+        .collect()
+}
+
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    flag_values(args, flag).into_iter().next()
+}
+
+// Builds a new note from flags alone, nothing is prompted for
+fn draft_from_args(
+    ctx: &ctx::Ctx,
+    args: &[String],
+    time: Option<chrono::DateTime<chrono::Local>>,
+) -> InquireResult<files::draft::Draft> {
+    let invalid = |message: String| InquireError::InvalidConfiguration(message);
+
+    let mut draft = files::draft::Draft::new(ctx);
+    if let Some(time) = time {
+        draft.time = time;
+    }
+
+    draft.title = flag_value(args, "--title").unwrap_or_default().to_string();
+    if files::name::slugify_title(&draft.title).is_empty() {
+        return Err(invalid(
+            "--title must have at least one letter or number".to_string(),
+        ));
+    }
+
+    // Keywords may be separated by spaces or commas
+    draft.keywords = flag_value(args, "--keywords")
+        .unwrap_or_default()
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .map(files::name::slugify_keyword)
+        .filter(|kw| !kw.is_empty())
+        .unique()
+        .collect();
+
+    draft.signature = flag_value(args, "--signature")
+        .map(files::name::slugify_signature)
+        .filter(|sig| !sig.is_empty());
+
+    if let Some(file_type) = flag_value(args, "--type") {
+        draft.file_type = files::types::FileType::from_name(file_type)
+            .ok_or_else(|| invalid(format!("Unknown note type: {}", file_type)))?;
+    }
+
+    if let Some(silo) = flag_value(args, "--silo") {
+        match ctx.opts.all_silos().into_iter().find(|s| s.name == silo) {
+            Some(silo) => draft.dir = silo.path,
+            None => return Err(invalid(format!("No silo named {}", silo))),
+        }
+    }
+
+    if let Some(template) = flag_value(args, "--template") {
+        let template = ctx
+            .opts
+            .all_templates()
+            .into_iter()
+            .find(|t| t.name == template)
+            .ok_or_else(|| invalid(format!("No template named {}", template)))?;
+
+        // Variables come from `--var name=value`, any not given are left empty
+        let given: Vec<(&str, &str)> = flag_values(args, "--var")
+            .into_iter()
+            .filter_map(|var| var.split_once('='))
+            .collect();
+
+        draft.answers = template
+            .variables
+            .iter()
+            .map(|variable| {
+                let value = given
+                    .iter()
+                    .find(|(name, _)| *name == variable.name)
+                    .map_or("", |(_, value)| value);
+                (variable.name.clone(), value.to_string())
+            })
+            .collect();
+        draft.template = Some(template);
+    }
+
+    Ok(draft)
+}
+
+// Previews, confirms and runs renames
+fn rename_all(plans: &[files::operations::RenamePlan]) -> InquireResult<()> {
+    if !prompts::confirm_renames(plans)? {
+        return Ok(());
+    }
+
+    files::operations::rename_files(plans)?;
+
+    for plan in plans {
+        println!(
+            "{} Renamed file: {:?} -> {}",
+            ">".magenta(),
+            plan.from,
+            plan.to.display().to_string().italic().magenta(),
+        );
+    }
+
+    Ok(())
+}
+
 // --- Rendering ---
 fn get_render_config() -> RenderConfig<'static> {
     let mut render_config = RenderConfig::default();
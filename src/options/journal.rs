@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+// Daily journal notes, one per day, found by keyword and the date in the identifier
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Journal {
+    #[serde(default = "default_keyword")]
+    pub keyword: String,
+    // A chrono format string, e.g. "%A %-d %B %Y" gives "Friday 22 March 2024"
+    #[serde(default = "default_title_format")]
+    pub title_format: String,
+    // The name of the template new entries start from
+    #[serde(default)]
+    pub template: Option<String>,
+    // The silo new entries go in, the default silo when unset
+    #[serde(default)]
+    pub silo: Option<String>,
+}
+
+impl Default for Journal {
+    fn default() -> Self {
+        Self {
+            keyword: default_keyword(),
+            title_format: default_title_format(),
+            template: None,
+            silo: None,
+        }
+    }
+}
+
+fn default_keyword() -> String {
+    "journal".to_string()
+}
+
+fn default_title_format() -> String {
+    "%A %-d %B %Y".to_string()
+}
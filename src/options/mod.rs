@@ -1,2 +1,5 @@
 pub mod opts;
 pub mod editor;
+pub mod silo;
+pub mod template;
+pub mod journal;
@@ -2,7 +2,7 @@ use std::{env, fs::{self, File, OpenOptions}, io::Write, path::PathBuf};
 use inquire::InquireError;
 use serde::{Deserialize, Serialize};
 
-use crate::{files::types, options::editor};
+use crate::{files::{name, types}, options::{editor, journal, silo, template}};
 
 
 // --- Basic CLI opts ---
@@ -11,6 +11,23 @@ pub(crate) struct Opts {
     pub opts_path: PathBuf,
     pub note_dir: PathBuf,
     pub notes_filetype: types::FileType,
+    // Write front matter exactly as Emacs Denote does, for vaults shared with Emacs
+    #[serde(default)]
+    pub denote_compatible: bool,
+    // Order of the file name components, e.g. title first for alphabetical listings
+    #[serde(default = "default_name_order")]
+    pub name_order: Vec<name::Component>,
+    // File or folder names to skip when loading notes, '*' and '?' wildcards are allowed
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    // Extra note directories, searched alongside `note_dir`
+    #[serde(default)]
+    pub silos: Vec<silo::Silo>,
+    // Note bodies, on top of the files in ~/.decoy/templates/
+    #[serde(default)]
+    pub templates: Vec<template::Template>,
+    #[serde(default)]
+    pub journal: journal::Journal,
     #[serde(default = "editor::Editor::default")]
     pub editor: editor::Editor,
 }
@@ -23,11 +40,50 @@ impl Default for Opts {
             opts_path: get_path(),
             note_dir: PathBuf::from(format!("{}/notes/", home)),
             notes_filetype: types::FileType::Markdown,
+            denote_compatible: false,
+            name_order: default_name_order(),
+            exclude: Vec::new(),
+            silos: Vec::new(),
+            templates: Vec::new(),
+            journal: journal::Journal::default(),
             editor: editor::Editor::default(),
         }
     }
 }
 
+impl Opts {
+    // `note_dir` is always the first silo, named silos follow it
+    pub fn all_silos(&self) -> Vec<silo::Silo> {
+        let default = silo::Silo {
+            name: silo::DEFAULT_SILO.to_string(),
+            path: self.note_dir.clone(),
+        };
+
+        std::iter::once(default)
+            .chain(self.silos.iter().filter(|s| s.path != self.note_dir).cloned())
+            .collect()
+    }
+
+    // Templates from the config first, then the ones in the templates folder
+    pub fn all_templates(&self) -> Vec<template::Template> {
+        let dir = self
+            .opts_path
+            .parent()
+            .map(|parent| parent.join("templates"))
+            .unwrap_or_default();
+
+        self.templates
+            .iter()
+            .cloned()
+            .chain(template::load_dir(&dir))
+            .collect()
+    }
+}
+
+fn default_name_order() -> Vec<name::Component> {
+    name::DEFAULT_ORDER.to_vec()
+}
+
 // --- Load things ---
 fn get_path() -> PathBuf {
     let home: String = env::var("HOME").unwrap_or_else(|_| ".".to_string());
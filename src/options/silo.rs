@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+// A named, separate note directory, e.g. work, personal or research
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct Silo {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+// The name given to `note_dir` when listed next to the configured silos
+pub(crate) const DEFAULT_SILO: &str = "default";
@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+// A named note body, e.g. a meeting or literature note skeleton
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct Template {
+    pub name: String,
+    pub body: String,
+    // Asked for before the note is written, filled into the body and the front matter
+    #[serde(default)]
+    pub variables: Vec<Variable>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) enum VariableKind {
+    #[default]
+    Text,
+    Select,
+    Date,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct Variable {
+    pub name: String,
+    #[serde(default)]
+    pub kind: VariableKind,
+    // The prompt shown to the user, defaults to the name
+    #[serde(default)]
+    pub prompt: Option<String>,
+    // The choices for a Select variable
+    #[serde(default)]
+    pub options: Vec<String>,
+}
+
+// Template files may open with a +++ fenced TOML block declaring their variables
+#[derive(Deserialize)]
+struct Declarations {
+    variables: Vec<Variable>,
+}
+
+// Read line by line, so templates saved with CRLF line endings declare variables too
+fn split_declarations(content: &str) -> (Vec<Variable>, String) {
+    let lines: Vec<&str> = content.lines().collect();
+
+    let declared = (lines.first() == Some(&"+++"))
+        .then(|| lines.iter().skip(1).position(|line| *line == "+++"))
+        .flatten()
+        .and_then(|close| {
+            let declarations: Declarations = toml::from_str(&lines[1..=close].join("\n")).ok()?;
+
+            let mut body = lines[close + 2..].join("\n");
+            if content.ends_with('\n') && !body.is_empty() {
+                body.push('\n');
+            }
+
+            Some((declarations.variables, body))
+        });
+
+    declared.unwrap_or_else(|| (Vec::new(), content.to_string()))
+}
+
+impl Template {
+    // Fills in {{key}} placeholders, unknown ones are left as they are
+    pub fn render(&self, vars: &[(&str, String)]) -> String {
+        vars.iter().fold(self.body.clone(), |body, (key, value)| {
+            body.replace(&format!("{{{{{}}}}}", key), value)
+        })
+    }
+}
+
+// Every file in the templates folder is a template named after its file stem
+pub(crate) fn load_dir(dir: &Path) -> Vec<Template> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut templates: Vec<Template> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_str()?.to_string();
+            if name.starts_with('.') {
+                return None;
+            }
+
+            let content = fs::read_to_string(&path).ok()?;
+            let (variables, body) = split_declarations(&content);
+
+            Some(Template {
+                name,
+                body,
+                variables,
+            })
+        })
+        .collect();
+
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    templates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn declarations_with_either_line_ending() {
+        for newline in ["\n", "\r\n"] {
+            let content = ["+++", "[[variables]]", "name = \"author\"", "+++", "# {{author}}", ""].join(newline);
+            let (variables, body) = split_declarations(&content);
+
+            assert_eq!(variables.len(), 1);
+            assert_eq!(variables[0].name, "author");
+            assert_eq!(body, "# {{author}}\n");
+        }
+    }
+
+    #[test]
+    fn templates_without_declarations_are_all_body() {
+        let content = "+++\ntitle = \"a Zola note\"\n+++\nBody\n";
+        assert_eq!(split_declarations(content), (Vec::new(), content.to_string()));
+    }
+}
@@ -1,16 +1,25 @@
-use chrono::Local;
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, Local, NaiveTime,
+};
+use colored::Colorize;
 use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
 use inquire::{
-    formatter::{MultiOptionFormatter, OptionFormatter},
+    formatter::MultiOptionFormatter,
+    list_option::ListOption,
     validator::Validation,
-    Autocomplete, DateSelect, InquireError, MultiSelect, Select, Text,
+    Autocomplete, Confirm, DateSelect, InquireError, MultiSelect, Select, Text,
 };
 use itertools::Itertools;
-use std::path::PathBuf;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 use crate::{
     ctx,
-    files::{frontmatter, note},
+    files::{draft, frontmatter, history, name, note, operations},
+    options::{silo, template},
 };
 
 // --- Auto complete ---
@@ -36,7 +45,7 @@ impl KeywordCompleter {
             })
             .collect();
 
-        matches.sort_by(|a, b| b.1.cmp(&a.1));
+        matches.sort_by_key(|m| std::cmp::Reverse(m.1));
         matches
     }
 }
@@ -88,14 +97,21 @@ fn option_split(input: &str) -> Option<()> {
 }
 
 // --- Prompts ---
-// Generate note with denote formmatter
-pub(crate) fn denote(ctx: &ctx::Ctx) -> Result<(PathBuf, frontmatter::FrontMatter), InquireError> {
-    // Input validators
-    let title_validator = |input: &str| match input.is_empty() {
-        true => Ok(Validation::Invalid("You must provide a title".into())),
+fn prompt_title(initial: &str) -> Result<String, InquireError> {
+    let title_validator = |input: &str| match name::slugify_title(input).is_empty() {
+        true => Ok(Validation::Invalid(
+            "You must provide a title with at least one letter or number".into(),
+        )),
         false => Ok(Validation::Valid),
     };
 
+    Text::new("New file TITLE:")
+        .with_initial_value(initial)
+        .with_validator(title_validator)
+        .prompt()
+}
+
+fn prompt_keywords(ctx: &ctx::Ctx, initial: &[String]) -> Result<Vec<String>, InquireError> {
     let kw_validator = |input: &str| match option_split(input) {
         Some(()) => Ok(Validation::Valid),
         None => Ok(Validation::Invalid(
@@ -103,60 +119,834 @@ pub(crate) fn denote(ctx: &ctx::Ctx) -> Result<(PathBuf, frontmatter::FrontMatte
         )),
     };
 
-    // Note generation
-    let gen_time_id = || Local::now().format("%Y%m%dT%H%M%S").to_string();
+    let keywords: String = Text::new("New file KEYWORDS:")
+        .with_initial_value(&initial.join(" "))
+        .with_help_message("↑↓ to move, <TAB> to autocomplete, type to filter, Tags are space separated and cannot contain '_' or '-'")
+        .with_autocomplete(KeywordCompleter::new(ctx.keywords.clone()))
+        .with_validator(kw_validator)
+        .prompt()?;
+
+    Ok(keywords
+        .split_whitespace()
+        .map(name::slugify_keyword)
+        .filter(|kw| !kw.is_empty())
+        .unique()
+        .collect())
+}
+
+fn prompt_signature(ctx: &ctx::Ctx, initial: &str) -> Result<Option<String>, InquireError> {
+    let sig_validator = |input: &str| match input.chars().all(|c| c.is_alphanumeric() || c == '=') {
+        true => Ok(Validation::Valid),
+        false => Ok(Validation::Invalid(
+            "Signatures may only contain letters, numbers and '='".into(),
+        )),
+    };
+
+    let signature: String = Text::new("New file SIGNATURE:")
+        .with_initial_value(initial)
+        .with_help_message("Optional, e.g. 1a2 for sequence notes. Leave empty to skip")
+        .with_autocomplete(KeywordCompleter::new(note::parse_all_signatures(&ctx.notes)))
+        .with_validator(sig_validator)
+        .prompt()?;
+
+    Ok(Some(name::slugify_signature(&signature)).filter(|sig| !sig.is_empty()))
+}
+
+fn prompt_silo(ctx: &ctx::Ctx) -> Result<silo::Silo, InquireError> {
+    let mut silos = ctx.opts.all_silos();
+
+    if silos.len() == 1 {
+        return Ok(silos.remove(0));
+    }
+
+    let names: Vec<String> = silos.iter().map(|s| s.name.clone()).collect();
+    let chosen = Select::new("Create note in silo:", names).raw_prompt()?;
+
+    Ok(silos.swap_remove(chosen.index))
+}
+
+// The notes of one silo, or every note when "All silos" is picked
+fn prompt_search_scope(ctx: &ctx::Ctx) -> Result<Vec<note::Note>, InquireError> {
+    let silos = ctx.opts.all_silos();
+
+    if silos.len() == 1 {
+        return Ok(ctx.notes.clone());
+    }
+
+    let all = "All silos".to_string();
+    let options: Vec<String> = std::iter::once(all.clone())
+        .chain(silos.into_iter().map(|s| s.name))
+        .collect();
+
+    let chosen = Select::new("Search in silo:", options).prompt()?;
+
+    match chosen == all {
+        true => Ok(ctx.notes.clone()),
+        false => Ok(note::search_by_silo(&ctx.notes, &chosen)),
+    }
+}
+
+fn prompt_template(ctx: &ctx::Ctx) -> Result<Option<template::Template>, InquireError> {
+    let mut templates = ctx.opts.all_templates();
 
-    let gen_date = || Local::now().format("%F %a %R").to_string();
+    if templates.is_empty() {
+        return Ok(None);
+    }
+
+    let options: Vec<String> = std::iter::once("No template".to_string())
+        .chain(templates.iter().map(|t| t.name.clone()))
+        .collect();
+
+    let chosen = Select::new("Note template:", options).raw_prompt()?;
+
+    Ok(match chosen.index {
+        0 => None,
+        i => Some(templates.swap_remove(i - 1)),
+    })
+}
 
-    let format_title = |title: String| title.split_whitespace().join("-").to_string();
+// Asks for each variable the template declares, in order
+fn prompt_variables(template: &template::Template) -> Result<Vec<(String, String)>, InquireError> {
+    let mut answers = Vec::with_capacity(template.variables.len());
+
+    for variable in &template.variables {
+        let message = format!("{}:", variable.prompt.as_deref().unwrap_or(&variable.name));
+
+        let answer = match variable.kind {
+            template::VariableKind::Text => Text::new(&message).prompt()?,
+            template::VariableKind::Select => {
+                Select::new(&message, variable.options.clone()).prompt()?
+            }
+            template::VariableKind::Date => DateSelect::new(&message)
+                .with_default(Local::now().date_naive())
+                .with_week_start(chrono::Weekday::Mon)
+                .prompt()?
+                .format("%F")
+                .to_string(),
+        };
+
+        answers.push((variable.name.clone(), answer));
+    }
 
-    let format_keywords = |kw: String| {
-        if !kw.is_empty() {
-            return format!("__{}", kw.split(' ').map(str::trim).join("_"));
+    Ok(answers)
+}
+
+// Picks a day from a calendar, then a time of day, for backdated notes
+pub(crate) fn prompt_date_time() -> Result<DateTime<Local>, InquireError> {
+    let date = DateSelect::new("Note date")
+        .with_default(Local::now().date_naive())
+        .with_week_start(chrono::Weekday::Mon)
+        .with_help_message("Use the arrow keys to select date")
+        .prompt()?;
+
+    let validator = |input: &str| {
+        match NaiveTime::parse_from_str(input.trim(), "%H:%M") {
+            Ok(_) => Ok(Validation::Valid),
+            Err(_) => Ok(Validation::Invalid("Use a 24 hour time like 14:30".into())),
         }
+    };
+
+    let time = Text::new("Note time:")
+        .with_default(&Local::now().format("%H:%M").to_string())
+        .with_validator(validator)
+        .prompt()?;
 
-        "".to_string()
+    // Already validated
+    let time = NaiveTime::parse_from_str(time.trim(), "%H:%M").unwrap_or_default();
+
+    Ok(draft::local_time(date, time))
+}
+
+// Generate note with denote formmatter, returns its path, front matter and body
+// The identifier and date come from `time` when given, otherwise from now
+pub(crate) fn denote(
+    ctx: &ctx::Ctx,
+    time: Option<DateTime<Local>>,
+) -> Result<(PathBuf, frontmatter::FrontMatter, String), InquireError> {
+    let mut draft = draft::Draft::new(ctx);
+    if let Some(time) = time {
+        draft.time = time;
+    }
+
+    draft.dir = prompt_silo(ctx)?.path;
+
+    prompt_draft(ctx, draft)
+}
+
+// The prompt, the signature is only asked for when the draft has none yet
+fn prompt_draft(
+    ctx: &ctx::Ctx,
+    mut draft: draft::Draft,
+) -> Result<(PathBuf, frontmatter::FrontMatter, String), InquireError> {
+    draft.title = prompt_title("")?;
+    draft.keywords = prompt_keywords(ctx, &[])?;
+    if draft.signature.is_none() {
+        draft.signature = prompt_signature(ctx, "")?;
+    }
+    draft.template = prompt_template(ctx)?;
+    if let Some(template) = &draft.template {
+        draft.answers = prompt_variables(template)?;
+    }
+
+    Ok(draft.prepare(ctx))
+}
+
+// --- Sequences ---
+// A child or sibling of a note in a sequence, numbered after its existing relatives
+pub(crate) fn sequence_note(
+    ctx: &ctx::Ctx,
+    relative: note::NewRelative,
+) -> Result<(PathBuf, frontmatter::FrontMatter, String), InquireError> {
+    let notes = prompt_search_scope(ctx)?;
+    let sequence = note::sequence_notes(&notes);
+
+    if sequence.is_empty() {
+        return Err(InquireError::InvalidConfiguration(
+            "No sequence notes yet, give a note the signature 1 to start one".to_string(),
+        ));
+    }
+
+    let message = match relative {
+        note::NewRelative::Child => "New child of:",
+        note::NewRelative::Sibling => "New sibling of:",
     };
+    let origin = Select::new(message, sequence).prompt()?;
+    let signature = origin
+        .name
+        .as_ref()
+        .and_then(|name| name.signature.as_deref())
+        .unwrap_or_default();
 
-    let identifier = gen_time_id();
+    let mut draft = draft::Draft::new(ctx);
+    draft.signature = Some(match relative {
+        note::NewRelative::Child => note::next_child(&notes, signature),
+        note::NewRelative::Sibling => note::next_sibling(&notes, signature),
+    });
 
-    // The prompt
-    let title: String = Text::new("New file TITLE:")
-        .with_validator(title_validator)
-        .prompt()
-        .unwrap();
+    // Relatives live next to each other
+    if let Some(dir) = origin.path.parent() {
+        draft.dir = dir.to_path_buf();
+    }
 
-    let keywords: String = Text::new("New file KEYWORDS:")
-        .with_help_message("↑↓ to move, <TAB> to autocomplete, type to filter, Tags are space separated and cannot contain '_' or '-'")
-        .with_autocomplete(KeywordCompleter::new(ctx.keywords.clone()))
-        .with_validator(kw_validator)
-        .prompt()
-        .unwrap();
-
-    let fmt = frontmatter::FrontMatter {
-        title: title.clone(),
-        date: gen_date(),
-        file_tags: keywords
-            .clone()
-            .split(' ')
-            .map(|kw| kw.to_string())
-            .collect(),
-        indentifier: identifier.clone(),
-    };
-
-    let note = format!(
-        "{}--{}{}{}",
-        identifier,
-        format_title(title),
-        format_keywords(keywords),
-        &ctx.opts.notes_filetype.as_ref()
+    prompt_draft(ctx, draft)
+}
+
+// Lists the parent, children or siblings of a note and returns the one picked
+pub(crate) fn search_notes_by_relation(
+    ctx: &ctx::Ctx,
+    relation: note::Relation,
+) -> Result<PathBuf, InquireError> {
+    let notes = prompt_search_scope(ctx)?;
+    let sequence = note::sequence_notes(&notes);
+
+    if sequence.is_empty() {
+        return Err(InquireError::InvalidConfiguration(
+            "No sequence notes yet, give a note the signature 1 to start one".to_string(),
+        ));
+    }
+
+    let origin = Select::new("Select note:", sequence).prompt()?;
+    let relatives = note::search_by_relation(&notes, &origin, relation);
+
+    let message = match relation {
+        note::Relation::Parent => "Parent:",
+        note::Relation::Children => "Children:",
+        note::Relation::Siblings => "Siblings:",
+    };
+
+    match relatives.is_empty() {
+        true => Err(InquireError::InvalidConfiguration(format!(
+            "No {} found",
+            message.trim_end_matches(':').to_lowercase()
+        ))),
+        false => Ok(Select::new(message, relatives).prompt()?.path),
+    }
+}
+
+// Finds the journal entry for a day, or prepares a new one when there is none yet
+pub(crate) fn journal(
+    ctx: &ctx::Ctx,
+    pick_date: bool,
+) -> Result<(PathBuf, Option<(frontmatter::FrontMatter, String)>), InquireError> {
+    let journal = &ctx.opts.journal;
+
+    let date = match pick_date {
+        true => DateSelect::new("Journal date")
+            .with_default(Local::now().date_naive())
+            .with_week_start(chrono::Weekday::Mon)
+            .with_help_message("Use the arrow keys to select date")
+            .prompt()?,
+        false => Local::now().date_naive(),
+    };
+
+    // Entries are written with the slug, so "Journal" finds "journal"
+    let keyword = name::slugify_keyword(&journal.keyword);
+    let entries = note::search_by_keywords(
+        &note::search_by_date(&ctx.notes, date),
+        vec![keyword.clone()],
     );
 
-    // Create the new file
-    let mut path = ctx.opts.note_dir.to_path_buf();
-    path.push(&note);
+    match entries.len() {
+        0 => {}
+        1 => return Ok((entries[0].path.clone(), None)),
+        _ => {
+            let entry = Select::new("Select journal entry:", entries).prompt()?;
+            return Ok((entry.path, None));
+        }
+    }
+
+    // Past days keep the time of day, so identifiers still sort in creation order
+    let time = draft::local_time(date, Local::now().time());
+
+    // chrono can't display a bad specifier, and to_string would panic on it
+    if StrftimeItems::new(&journal.title_format).any(|item| matches!(item, Item::Error)) {
+        return Err(InquireError::InvalidConfiguration(format!(
+            "Invalid journal title_format: {}",
+            journal.title_format
+        )));
+    }
+
+    // The full time, so formats with hours and minutes work too
+    let mut draft = draft::Draft::new(ctx);
+    draft.time = time;
+    draft.title = time.format(&journal.title_format).to_string();
+    draft.keywords = vec![keyword];
+
+    if let Some(silo) = &journal.silo {
+        match ctx.opts.all_silos().into_iter().find(|s| &s.name == silo) {
+            Some(silo) => draft.dir = silo.path,
+            None => return Err(InquireError::InvalidConfiguration(format!("No silo named {}", silo))),
+        }
+    }
+
+    if let Some(template) = &journal.template {
+        match ctx.opts.all_templates().into_iter().find(|t| &t.name == template) {
+            Some(template) => {
+                draft.answers = prompt_variables(&template)?;
+                draft.template = Some(template);
+            }
+            None => {
+                return Err(InquireError::InvalidConfiguration(format!(
+                    "No template named {}",
+                    template
+                )))
+            }
+        }
+    }
+
+    let (path, fmt, body) = draft.prepare(ctx);
+    Ok((path, Some((fmt, body))))
+}
+
+// Generate a new denote name for an existing file, keeping its identifier and signature
+// Returns the new name and the title as typed, for the front matter
+pub(crate) fn rename(
+    ctx: &ctx::Ctx,
+    note: &note::Note,
+) -> Result<(name::DenoteName, String), InquireError> {
+    let old = note.name.clone().unwrap_or_default();
+    let initial_title = initial_title(note);
+
+    let kept_identifier = old.identifier.is_some();
+    let identifier = match old.identifier {
+        Some(identifier) => identifier,
+        None => prompt_identifier(&note.path)?,
+    };
+
+    let title = prompt_title(&initial_title)?;
+    let keywords = prompt_keywords(ctx, &old.keywords)?;
+    let signature = match old.signature {
+        Some(signature) => Some(signature),
+        None => prompt_signature(ctx, "")?,
+    };
+
+    let mut name = name::DenoteName {
+        identifier: Some(identifier),
+        signature,
+        title: Some(name::slugify_title(&title)),
+        keywords,
+        extension: extension_of(note),
+        order: order_of(ctx, note),
+    };
+
+    name::warn_if_shortened(&mut name);
+
+    // Only a freshly made identifier may clash with another note
+    if !kept_identifier {
+        let dir = note.path.parent().unwrap_or(&ctx.opts.note_dir);
+        note::ensure_unique(&ctx.notes, dir, &mut name);
+    }
+
+    Ok((name, title))
+}
+
+// A denote note picked by keywords, or any other file in the silos
+pub(crate) fn search_files_to_rename(ctx: &ctx::Ctx) -> Result<PathBuf, InquireError> {
+    if ctx.other_files.is_empty() {
+        return search_notes_by_keywords(ctx);
+    }
+
+    let notes = "A note, by keywords";
+    let others = format!("A file without a denote name ({})", ctx.other_files.len());
+    let source = Select::new("Rename:", vec![notes.to_string(), others]).prompt()?;
+
+    match source == notes {
+        true => search_notes_by_keywords(ctx),
+        false => Ok(Select::new("Select file:", ctx.other_files.clone()).prompt()?.path),
+    }
+}
+
+// A new identifier for a file without one, from a date in its name, its modification time or now
+fn prompt_identifier(path: &Path) -> Result<String, InquireError> {
+    let sources = draft::identifier_sources(path)?;
+
+    let options: Vec<String> = sources
+        .iter()
+        .map(|(label, time)| format!("{} ({})", label, time.format("%F %R")))
+        .collect();
+
+    let chosen = Select::new("The file has no identifier, create one from:", options).raw_prompt()?;
+
+    Ok(sources[chosen.index].1.format(name::ID_FORMAT).to_string())
+}
+
+// Denote names keep the order they were written in, other files get the configured one
+fn order_of(ctx: &ctx::Ctx, note: &note::Note) -> [name::Component; 4] {
+    note.name
+        .as_ref()
+        .map_or_else(|| name::normalise_order(&ctx.opts.name_order), |name| name.order)
+}
+
+// The title to prefill when renaming or importing a file
+fn initial_title(note: &note::Note) -> String {
+    // The real title lives in the front matter, the file name only has its slug
+    let front_matter_title = operations::read_front_matter(&note.path)
+        .ok()
+        .flatten()
+        .map(|(fm, _)| fm.title)
+        .filter(|title| !title.is_empty());
+
+    // Files outside the denote system start from their plain file name
+    match (note.name.as_ref().and_then(|name| name.title.as_ref()), front_matter_title) {
+        (_, Some(title)) => title,
+        (Some(title), None) => title.replace('-', " "),
+        (None, None) if note.name.is_none() => note
+            .path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default()
+            .to_string(),
+        (None, None) => String::new(),
+    }
+}
+
+fn extension_of(note: &note::Note) -> String {
+    match &note.name {
+        Some(name) => name.extension.clone(),
+        None => note
+            .path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| format!(".{}", ext))
+            .unwrap_or_default(),
+    }
+}
+
+// --- Importing ---
+// Gives any file a Denote name in a silo, returns the plan and whether to copy rather than move
+// None when the import is not confirmed
+pub(crate) fn import(
+    ctx: &ctx::Ctx,
+    source: Option<&str>,
+) -> Result<Option<(operations::RenamePlan, bool)>, InquireError> {
+    let file_validator = |input: &str| match PathBuf::from(input.trim()).is_file() {
+        true => Ok(Validation::Valid),
+        false => Ok(Validation::Invalid("No file at that path".into())),
+    };
+
+    let source = match source {
+        Some(source) => source.to_string(),
+        None => Text::new("File to import:")
+            .with_validator(file_validator)
+            .prompt()?,
+    };
+    let source = PathBuf::from(source.trim());
+
+    if !source.is_file() {
+        return Err(InquireError::InvalidConfiguration(format!(
+            "No file at {}",
+            source.display()
+        )));
+    }
+
+    let note = note::Note::new(source.clone());
+    let old = note.name.clone().unwrap_or_default();
+
+    // Files already in the Denote format keep their identifier
+    let kept_identifier = old.identifier.is_some();
+    let identifier = match old.identifier {
+        Some(identifier) => identifier,
+        None => prompt_identifier(&source)?,
+    };
+
+    let silo = prompt_silo(ctx)?;
+    let title = prompt_title(&initial_title(&note))?;
+    let keywords = prompt_keywords(ctx, &old.keywords)?;
+    let signature = match old.signature {
+        Some(signature) => Some(signature),
+        None => prompt_signature(ctx, "")?,
+    };
+
+    let mut name = name::DenoteName {
+        identifier: Some(identifier),
+        signature,
+        title: Some(name::slugify_title(&title)),
+        keywords,
+        extension: extension_of(&note),
+        order: order_of(ctx, &note),
+    };
+
+    name::warn_if_shortened(&mut name);
+
+    let copy = "Copy, leaving the original in place";
+    let action = Select::new("Import by:", vec!["Move", copy]).prompt()?;
+
+    // A moved note doesn't clash with itself, but a copy of it would share its identifier
+    let source_path = fs::canonicalize(&source)?;
+    let others: Vec<note::Note> = ctx
+        .notes
+        .iter()
+        .filter(|other| action == copy || fs::canonicalize(&other.path).ok() != Some(source_path.clone()))
+        .cloned()
+        .collect();
+
+    // Kept identifiers clash too, when importing from another vault or copying within this one
+    let wanted = name.identifier.clone();
+    note::ensure_unique(&others, &silo.path, &mut name);
+
+    if kept_identifier && name.identifier != wanted {
+        eprintln!(
+            "{} The identifier {} is already taken, using {} instead",
+            "#".red(),
+            wanted.unwrap_or_default(),
+            name.identifier.as_deref().unwrap_or_default().italic().magenta(),
+        );
+    }
+
+    let plan = operations::plan_move(&source, &silo.path, &name, Some(&title))?;
+
+    println!("  {}", plan.from.display().to_string().red());
+    println!("  {} {}", "->".magenta(), plan.to.display().to_string().green());
+
+    let confirmed = Confirm::new("Import file?").with_default(true).prompt()?;
+
+    Ok(confirmed.then_some((plan, action == copy)))
+}
+
+// Plan renames from front matter, for one note or a whole silo
+pub(crate) fn rename_from_front_matter(
+    ctx: &ctx::Ctx,
+) -> Result<Vec<operations::RenamePlan>, InquireError> {
+    let one = "One note";
+    let scope = Select::new("Rename from front matter:", vec![one, "Every note in a silo"]).prompt()?;
+
+    let notes = match scope == one {
+        true => vec![note::Note::new(search_notes_by_keywords(ctx)?)],
+        false => prompt_search_scope(ctx)?,
+    };
+
+    let mut plans = Vec::new();
+    for note in &notes {
+        let Some((fm, _)) = operations::read_front_matter(&note.path)? else {
+            continue;
+        };
+        let Some(new_name) = note::name_from_front_matter(note, &fm) else {
+            continue;
+        };
+
+        // The title is already right, only the keywords and identifier may be normalised
+        let plan = operations::plan_rename(&note.path, &new_name, None)?;
+        if plan.to != plan.from || plan.content.is_some() {
+            plans.push(plan);
+        }
+    }
+
+    if plans.is_empty() {
+        println!("{} File names already match their front matter", ">".magenta());
+    }
+
+    Ok(plans)
+}
+
+// Change only the title, keywords or signature of a note, everything else in its name stays
+pub(crate) fn edit(
+    ctx: &ctx::Ctx,
+    part: name::Component,
+) -> Result<Vec<operations::RenamePlan>, InquireError> {
+    let note = note::Note::new(search_notes_by_keywords(ctx)?);
+
+    let Some(mut new_name) = note.name.clone() else {
+        return Err(InquireError::InvalidConfiguration(
+            "The file name is not in the Denote format, use --rename first".to_string(),
+        ));
+    };
+
+    let mut title = None;
+    match part {
+        name::Component::Title => {
+            let typed = prompt_title(&initial_title(&note))?;
+            new_name.title = Some(name::slugify_title(&typed));
+            title = Some(typed);
+        }
+        name::Component::Keywords => {
+            let current = new_name.keywords.clone();
+            let all: Vec<usize> = (0..current.len()).collect();
+
+            let mut keywords = match current.is_empty() {
+                true => Vec::new(),
+                false => MultiSelect::new("Keep keywords:", current)
+                    .with_default(&all)
+                    .with_help_message("↑↓ to move, <SPACE> to drop or keep a keyword")
+                    .prompt()?,
+            };
+
+            for keyword in prompt_keywords(ctx, &[])? {
+                if !keywords.contains(&keyword) {
+                    keywords.push(keyword);
+                }
+            }
+
+            new_name.keywords = keywords;
+        }
+        name::Component::Signature => {
+            let current = new_name.signature.clone().unwrap_or_default();
+            new_name.signature = prompt_signature(ctx, &current)?;
+        }
+        // Identifiers are what links point at, so they are never edited
+        name::Component::Identifier => {}
+    }
+
+    name::warn_if_shortened(&mut new_name);
+
+    let plan = operations::plan_rename(&note.path, &new_name, title.as_deref())?;
+    match plan.to != plan.from || plan.content.is_some() {
+        true => Ok(vec![plan]),
+        false => {
+            println!("{} Nothing to rename", ">".magenta());
+            Ok(Vec::new())
+        }
+    }
+}
+
+// Plan one change to the names of many notes
+pub(crate) fn rename_batch(ctx: &ctx::Ctx) -> Result<Vec<operations::RenamePlan>, InquireError> {
+    let note_formatter: MultiOptionFormatter<note::Note> = &|a| format!("{} note(s)", a.len());
+
+    let notes: Vec<note::Note> = prompt_search_scope(ctx)?
+        .into_iter()
+        .filter(|note| note.name.is_some())
+        .collect();
+
+    let notes = MultiSelect::new("Select notes:", notes)
+        .with_formatter(note_formatter)
+        .with_help_message("↑↓ to move, <SPACE> to select, type to filter")
+        .prompt()?;
+
+    let add_keyword = "Add a keyword";
+    let remove_keyword = "Remove a keyword";
+    let change = Select::new(
+        "Change:",
+        vec![add_keyword, remove_keyword, "Add a signature prefix"],
+    )
+    .prompt()?;
+
+    let edit: Box<dyn Fn(&mut name::DenoteName)> = match change {
+        c if c == add_keyword => {
+            let keyword = Text::new("Keyword to add:")
+                .with_autocomplete(KeywordCompleter::new(ctx.keywords.clone()))
+                .prompt()?;
+            let keyword = name::slugify_keyword(&keyword);
+
+            Box::new(move |name| {
+                if !keyword.is_empty() && !name.keywords.contains(&keyword) {
+                    name.keywords.push(keyword.clone());
+                }
+            })
+        }
+        c if c == remove_keyword => {
+            let keyword = Select::new("Keyword to remove:", note::parse_all_keywords(&notes))
+                .prompt()?;
+
+            Box::new(move |name| name.keywords.retain(|kw| *kw != keyword))
+        }
+        _ => {
+            let prefix = Text::new("Signature prefix:")
+                .with_help_message("Joined to existing signatures with '=', so 2 turns 1a into 2=1a")
+                .prompt()?;
+            let prefix = name::slugify_signature(&prefix);
+
+            Box::new(move |name| {
+                if !prefix.is_empty() {
+                    name.signature = Some(match &name.signature {
+                        Some(signature) => format!("{}={}", prefix, signature),
+                        None => prefix.clone(),
+                    });
+                }
+            })
+        }
+    };
+
+    let mut plans = Vec::new();
+    for note in &notes {
+        let Some(mut new_name) = note.name.clone() else {
+            continue;
+        };
+        edit(&mut new_name);
+        name::warn_if_shortened(&mut new_name);
+
+        // Notes the change doesn't touch are left out of the table
+        if Some(&new_name) != note.name.as_ref() {
+            plans.push(operations::plan_rename(&note.path, &new_name, None)?);
+        }
+    }
+
+    if plans.is_empty() {
+        println!("{} Nothing to rename", ">".magenta());
+    }
+
+    Ok(plans)
+}
+
+// --- Undo ---
+// The last operation, or one picked from the history, once confirmed
+// Returns its index in the history
+pub(crate) fn undo(pick: bool) -> Result<Option<(usize, history::Entry)>, InquireError> {
+    // Newest first, operations already undone can't be undone twice
+    let mut operations: Vec<(usize, history::Entry)> = history::load()?
+        .into_iter()
+        .enumerate()
+        .filter(|(_, entry)| !entry.undone)
+        .collect();
+    operations.reverse();
+
+    if operations.is_empty() {
+        println!("{} Nothing to undo", ">".magenta());
+        return Ok(None);
+    }
+
+    let chosen = match pick {
+        true => {
+            let options: Vec<String> = operations.iter().map(|(_, entry)| entry.to_string()).collect();
+            Select::new("Undo operation:", options).raw_prompt()?.index
+        }
+        false => 0,
+    };
+    let (index, entry) = operations.swap_remove(chosen);
+
+    // Refuse before asking, rather than after
+    operations::check_undo(&entry)?;
+
+    let message = match (entry.action, entry.changes.as_slice()) {
+        (history::Action::Write | history::Action::Copy, [change]) => {
+            format!("Undo and delete {:?}?", change.to)
+        }
+        _ => "Undo?".to_string(),
+    };
+
+    println!("  {}", entry);
+    // A batch lists every file it puts back
+    if entry.changes.len() > 1 {
+        for change in &entry.changes {
+            println!("    {}", change);
+        }
+    }
+    let confirmed = Confirm::new(&message).with_default(false).prompt()?;
+
+    Ok(confirmed.then_some((index, entry)))
+}
+
+// Shows every planned rename and front matter diff, then asks once
+pub(crate) fn confirm_renames(plans: &[operations::RenamePlan]) -> Result<bool, InquireError> {
+    if plans.is_empty() {
+        return Ok(false);
+    }
+
+    let file_name = |path: &PathBuf| {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("<invalid>")
+            .to_string()
+    };
+
+    // Old and new names in two columns, padded to the longest old name
+    let width = plans
+        .iter()
+        .map(|plan| file_name(&plan.from).chars().count())
+        .max()
+        .unwrap_or_default();
+
+    for plan in plans {
+        let old = format!("{:<width$}", file_name(&plan.from));
+        println!("  {} {} {}", old.red(), "->".magenta(), file_name(&plan.to).green());
+
+        if let Some((old, new)) = &plan.content {
+            for (sign, line) in line_diff(old, new) {
+                match sign {
+                    '-' => println!("    {}", format!("- {}", line).red()),
+                    _ => println!("    {}", format!("+ {}", line).green()),
+                }
+            }
+        }
+    }
+
+    Confirm::new(&format!("Rename {} file(s)?", plans.len()))
+        .with_default(false)
+        .prompt()
+}
+
+// Removed and added lines between two texts, unchanged lines are left out
+fn line_diff<'a>(old: &'a str, new: &'a str) -> Vec<(char, &'a str)> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
 
-    Ok((path, fmt))
+    // Only the front matter changes, so skip the shared start and end
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old = &old[prefix..old.len() - suffix];
+    let new = &new[prefix..new.len() - suffix];
+
+    // Longest common subsequence over what is left
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = match old[i] == new[j] {
+                true => lcs[i + 1][j + 1] + 1,
+                false => lcs[i + 1][j].max(lcs[i][j + 1]),
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut diff = Vec::new();
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            diff.push(('-', old[i]));
+            i += 1;
+        } else {
+            diff.push(('+', new[j]));
+            j += 1;
+        }
+    }
+
+    diff
 }
 
 // Search notes by keywords
@@ -172,56 +962,89 @@ pub(crate) fn search_notes_by_keywords(ctx: &ctx::Ctx) -> Result<PathBuf, Inquir
         )
     };
 
-    let note_formatter: OptionFormatter<note::Note> = &|a| {
-        let formatted = a
-            .value
-            .0
-            .file_stem()
-            .and_then(|os_str| os_str.to_str())
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| "<invalid>".to_string());
+    // Prompt
+    let notes = prompt_search_scope(ctx)?;
 
-        formatted
-    };
+    let keywords = note::parse_all_keywords(&notes);
+    if keywords.is_empty() {
+        return Err(InquireError::InvalidConfiguration(
+            "No notes with keywords to search".to_string(),
+        ));
+    }
 
-    // Prompt
-    let kws = MultiSelect::new("Select relavent keywords:", ctx.keywords.clone())
+    let kws = MultiSelect::new("Select relavent keywords:", keywords)
         .with_formatter(kw_formatter)
-        .prompt()
-        .unwrap();
+        .prompt()?;
 
-    let note = Select::new("Select note:", note::search_by_keywords(&ctx.notes, kws))
-        .with_formatter(note_formatter)
-        .prompt()
-        .unwrap();
+    let matches = note::search_by_keywords(&notes, kws.clone());
+    if matches.is_empty() {
+        return Err(InquireError::InvalidConfiguration(format!(
+            "No notes tagged with {}",
+            kws.join(" ")
+        )));
+    }
+
+    let note = Select::new("Select note:", matches)
+        .with_formatter(&format_note)
+        .prompt()?;
 
-    Ok(note.0)
+    Ok(note.path)
 }
 
 pub(crate) fn search_notes_by_date(ctx: &ctx::Ctx) -> Result<PathBuf, InquireError> {
-    let note_formatter: OptionFormatter<note::Note> = &|a| {
-        let formatted = a
-            .value
-            .0
-            .file_stem()
-            .and_then(|os_str| os_str.to_str())
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| "<invalid>".to_string());
-
-        formatted
-    };
+    let notes = prompt_search_scope(ctx)?;
 
     let date = DateSelect::new("Selected date")
         .with_default(Local::now().date_naive())
         .with_week_start(chrono::Weekday::Mon)
         .with_help_message("Use the arrow keys to select date")
-        .prompt()
-        .unwrap();
+        .prompt()?;
 
-    let note = Select::new("Select note:", note::search_by_date(&ctx.notes, date))
-        .with_formatter(note_formatter)
-        .prompt()
-        .unwrap();
+    let matches = note::search_by_date(&notes, date);
+    if matches.is_empty() {
+        return Err(InquireError::InvalidConfiguration(format!(
+            "No notes from {}",
+            date.format("%F")
+        )));
+    }
+
+    let note = Select::new("Select note:", matches)
+        .with_formatter(&format_note)
+        .prompt()?;
+
+    Ok(note.path)
+}
+
+pub(crate) fn search_notes_by_signature(ctx: &ctx::Ctx) -> Result<PathBuf, InquireError> {
+    let notes = prompt_search_scope(ctx)?;
+
+    let signature: String = Text::new("Signature:")
+        .with_help_message("<TAB> to autocomplete, matches every signature starting with the input")
+        .with_autocomplete(KeywordCompleter::new(note::parse_all_signatures(&notes)))
+        .prompt()?;
+
+    let matches = note::search_by_signature(&notes, signature.trim());
+    if matches.is_empty() {
+        return Err(InquireError::InvalidConfiguration(format!(
+            "No notes with a signature starting with {}",
+            signature.trim()
+        )));
+    }
+
+    let note = Select::new("Select note:", matches)
+        .with_formatter(&format_note)
+        .prompt()?;
+
+    Ok(note.path)
+}
 
-    Ok(note.0)
+// Picked notes are shown by their file stem
+fn format_note(option: ListOption<&note::Note>) -> String {
+    option
+        .value
+        .path
+        .file_stem()
+        .and_then(|os_str| os_str.to_str())
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| "<invalid>".to_string())
 }